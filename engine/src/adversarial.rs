@@ -0,0 +1,91 @@
+use crate::MLP;
+use crate::util::sign;
+use crate::value::Value;
+
+// Gradient of the squared error between the first output and target with
+// respect to each input. Input grads are reset first; parameter grads are
+// accumulated into as a side effect of the backward pass.
+pub fn input_gradient(mlp: &MLP, x: &[Value], target: &Value) -> Vec<f64> {
+    for xi in x {
+        xi.zero_grad();
+    }
+    let pred = mlp.forward(x)[0].clone();
+    let diff = pred - target.clone();
+    let loss = diff.clone() * diff;
+    loss.backward();
+    x.iter().map(|xi| xi.grad()).collect()
+}
+
+// Fast gradient sign method: moves each input by epsilon in the direction
+// that increases the squared error, i.e. x + epsilon * sign(grad).
+// Parameter grads are zeroed afterwards.
+pub fn fgsm(mlp: &MLP, x: &[f64], target: f64, epsilon: f64) -> Vec<f64> {
+    let inputs: Vec<Value> = x.iter().map(|&xi| Value::new(xi)).collect();
+    let grads = input_gradient(mlp, &inputs, &Value::new(target));
+    mlp.zero_grad();
+    x.iter()
+        .zip(grads)
+        .map(|(&xi, g)| xi + epsilon * sign(g))
+        .collect()
+}
+
+// Absolute gradient of the first output with respect to each input feature.
+// Parameter grads are zeroed afterwards.
+pub fn saliency(mlp: &MLP, x: &[f64]) -> Vec<f64> {
+    let inputs: Vec<Value> = x.iter().map(|&xi| Value::new(xi)).collect();
+    mlp.forward(&inputs)[0].backward();
+    mlp.zero_grad();
+    inputs.iter().map(|xi| xi.grad().abs()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Activation;
+
+    // y = 2 * x1 - 3 * x2
+    fn linear() -> MLP {
+        MLP::from_weights(
+            vec![vec![vec![2.0, -3.0]]],
+            vec![vec![0.0]],
+            vec![Activation::Linear],
+        )
+    }
+
+    #[test]
+    fn input_gradient_has_expected_sign() {
+        let x = [Value::new(1.0), Value::new(1.0)];
+        // pred = -1 is below the target, so d/dx = 2 * (pred - target) * w.
+        let grads = input_gradient(&linear(), &x, &Value::new(1.0));
+        assert_eq!(grads, vec![-8.0, 12.0]);
+    }
+
+    #[test]
+    fn fgsm_moves_each_input_by_epsilon_and_raises_loss() {
+        let mlp = linear();
+        let x = [0.5, 0.2];
+        let target = 1.0;
+        let adv = fgsm(&mlp, &x, target, 0.1);
+        for (a, b) in adv.iter().zip(x.iter()) {
+            assert!(((a - b).abs() - 0.1).abs() < 1e-12);
+        }
+        let loss = |x: &[f64]| {
+            let inputs: Vec<Value> = x.iter().map(|&xi| Value::new(xi)).collect();
+            (mlp.forward(&inputs)[0].data() - target).powi(2)
+        };
+        assert!(loss(&adv) > loss(&x));
+        assert!(mlp.parameters().iter().all(|p| p.grad() == 0.0));
+    }
+
+    #[test]
+    fn saliency_highlights_dominant_input() {
+        let mlp = MLP::from_weights(
+            vec![vec![vec![0.1, 5.0, -0.2]]],
+            vec![vec![0.0]],
+            vec![Activation::Linear],
+        );
+        let s = saliency(&mlp, &[1.0, 1.0, 1.0]);
+        assert_eq!(s, vec![0.1, 5.0, 0.2]);
+        assert!(mlp.parameters().iter().all(|p| p.grad() == 0.0));
+    }
+}
@@ -0,0 +1,192 @@
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_distr::{Beta, Distribution};
+use std::fs;
+use std::io;
+
+// Reads a headerless numeric CSV, splitting column target_col out as the
+// target and keeping the remaining columns as features. Blank lines are skipped.
+pub fn load_csv(path: &str, target_col: usize) -> io::Result<(Vec<Vec<f64>>, Vec<f64>)> {
+    let contents = fs::read_to_string(path)?;
+    let mut features = Vec::new();
+    let mut targets = Vec::new();
+
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut row = Vec::new();
+        for field in line.split(',') {
+            let field = field.trim();
+            let val = field.parse::<f64>().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: could not parse '{}' as a number", i + 1, field),
+                )
+            })?;
+            row.push(val);
+        }
+
+        if target_col >= row.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "line {}: target column {} out of range for {} columns",
+                    i + 1,
+                    target_col,
+                    row.len()
+                ),
+            ));
+        }
+        targets.push(row.remove(target_col));
+        features.push(row);
+    }
+
+    Ok((features, targets))
+}
+
+// Mixes each sample with a randomly chosen partner: x = lam * x_i + (1 - lam) * x_j
+// (and likewise for y), with lam drawn from Beta(alpha, alpha). Small alpha
+// pushes lam towards 0 or 1, so outputs stay close to one of the originals.
+// A non-positive alpha disables mixing and returns copies of the inputs.
+pub fn mixup(xs: &[Vec<f64>], ys: &[f64], alpha: f64, seed: u64) -> (Vec<Vec<f64>>, Vec<f64>) {
+    if alpha <= 0.0 {
+        return (xs.to_vec(), ys.to_vec());
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let beta = Beta::new(alpha, alpha).expect("alpha must be finite");
+    let mut mixed_xs = Vec::with_capacity(xs.len());
+    let mut mixed_ys = Vec::with_capacity(ys.len());
+
+    for i in 0..xs.len() {
+        let j = rng.random_range(0..xs.len());
+        let lam = beta.sample(&mut rng);
+        let x = xs[i]
+            .iter()
+            .zip(xs[j].iter())
+            .map(|(a, b)| lam * a + (1.0 - lam) * b)
+            .collect();
+        mixed_xs.push(x);
+        mixed_ys.push(lam * ys[i] + (1.0 - lam) * ys[j]);
+    }
+
+    (mixed_xs, mixed_ys)
+}
+
+// Per-feature mean and population variance of a stream of samples, updated
+// one sample at a time with Welford's algorithm.
+#[derive(Debug, Clone, Default)]
+pub struct RunningStats {
+    count: usize,
+    mean: Vec<f64>,
+    m2: Vec<f64>,
+}
+
+impl RunningStats {
+    pub fn new() -> RunningStats {
+        RunningStats::default()
+    }
+
+    pub fn update(&mut self, x: &[f64]) {
+        if self.count == 0 {
+            self.mean = vec![0.0; x.len()];
+            self.m2 = vec![0.0; x.len()];
+        }
+        assert_eq!(
+            x.len(),
+            self.mean.len(),
+            "expected {} features, got {}",
+            self.mean.len(),
+            x.len()
+        );
+        self.count += 1;
+        for ((mean, m2), &xi) in self.mean.iter_mut().zip(self.m2.iter_mut()).zip(x.iter()) {
+            let delta = xi - *mean;
+            *mean += delta / self.count as f64;
+            *m2 += delta * (xi - *mean);
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> Vec<f64> {
+        self.mean.clone()
+    }
+
+    pub fn variance(&self) -> Vec<f64> {
+        if self.count == 0 {
+            return self.m2.clone();
+        }
+        self.m2.iter().map(|m2| m2 / self.count as f64).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn temp_file(name: &str, contents: &str) -> String {
+        let path = env::temp_dir().join(format!("engine_{}_{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn load_csv_splits_target_and_reports_errors() {
+        let path = temp_file("ok.csv", "1.0, 2.0, 3.0\n\n4.0,5.0,6.0\n");
+        let (features, targets) = load_csv(&path, 1).unwrap();
+        assert_eq!(features, vec![vec![1.0, 3.0], vec![4.0, 6.0]]);
+        assert_eq!(targets, vec![2.0, 5.0]);
+
+        let bad = temp_file("bad.csv", "1.0,2.0\n3.0,abc\n");
+        let err = load_csv(&bad, 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("line 2"));
+        assert!(load_csv(&path, 5).is_err());
+
+        fs::remove_file(path).unwrap();
+        fs::remove_file(bad).unwrap();
+    }
+
+    #[test]
+    fn mixup_stays_in_hull_and_vanishes_with_small_alpha() {
+        let xs = vec![vec![0.0, 10.0], vec![1.0, 20.0], vec![2.0, 30.0]];
+        let ys = vec![0.0, 1.0, 2.0];
+        let (mx, my) = mixup(&xs, &ys, 0.4, 7);
+        assert_eq!(mx.len(), 3);
+        for (x, &y) in mx.iter().zip(my.iter()) {
+            assert!((0.0..=2.0).contains(&x[0]) && (10.0..=30.0).contains(&x[1]));
+            assert!((0.0..=2.0).contains(&y));
+        }
+
+        assert_eq!(mixup(&xs, &ys, 0.0, 7), (xs.clone(), ys.clone()));
+        // As alpha -> 0 the mixing coefficient concentrates at 0 or 1, so
+        // every mixed sample is (nearly) one of the originals.
+        let (mx, _) = mixup(&xs, &ys, 1e-3, 7);
+        for x in &mx {
+            assert!(xs.iter().any(|orig| (orig[0] - x[0]).abs() < 1e-2));
+        }
+    }
+
+    #[test]
+    fn running_stats_match_batch_mean_and_variance() {
+        let samples = [[1.0, -2.0], [4.0, 0.5], [2.5, 3.0], [-1.0, 1.0], [0.5, 0.0]];
+        let mut stats = RunningStats::new();
+        for s in &samples {
+            stats.update(s);
+        }
+        assert_eq!(stats.count(), samples.len());
+        let n = samples.len() as f64;
+        for j in 0..2 {
+            let mean = samples.iter().map(|s| s[j]).sum::<f64>() / n;
+            let variance = samples.iter().map(|s| (s[j] - mean).powi(2)).sum::<f64>() / n;
+            assert!((stats.mean()[j] - mean).abs() < 1e-12);
+            assert!((stats.variance()[j] - variance).abs() < 1e-12);
+        }
+    }
+}
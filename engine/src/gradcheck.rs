@@ -0,0 +1,257 @@
+use crate::MLP;
+use crate::loss::batch_loss;
+use crate::value::Value;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+fn eval_at(f: &impl Fn(&[Value]) -> Value, point: &[f64]) -> f64 {
+    let inputs: Vec<Value> = point.iter().map(|&x| Value::new(x)).collect();
+    f(&inputs).data()
+}
+
+// Central second-order finite difference of f along each input:
+// (f(x + eps) - 2f(x) + f(x - eps)) / eps^2. Only the data of inputs is read;
+// f is evaluated on fresh Values so no gradients are touched.
+pub fn hessian_diagonal(f: impl Fn(&[Value]) -> Value, inputs: &[Value], eps: f64) -> Vec<f64> {
+    let point: Vec<f64> = inputs.iter().map(|v| v.data()).collect();
+    let center = eval_at(&f, &point);
+
+    let mut diag = Vec::with_capacity(point.len());
+    for i in 0..point.len() {
+        let mut plus = point.clone();
+        plus[i] += eps;
+        let mut minus = point.clone();
+        minus[i] -= eps;
+        diag.push((eval_at(&f, &plus) - 2.0 * center + eval_at(&f, &minus)) / (eps * eps));
+    }
+    diag
+}
+
+fn batch_grad(mlp: &MLP, xs: &[Vec<Value>], ys: &[Value]) -> Vec<f64> {
+    mlp.zero_grad();
+    batch_loss(mlp, xs, ys).backward();
+    mlp.grad_flat()
+}
+
+// Hessian-vector product of batch_loss with respect to the parameters,
+// approximated by the forward difference (grad(w + eps * v) - grad(w)) / eps.
+// Parameters are restored afterwards and their grads left zeroed.
+pub fn hvp(mlp: &MLP, xs: &[Vec<Value>], ys: &[Value], vec: &[f64], eps: f64) -> Vec<f64> {
+    let original = mlp.parameters_flat();
+    assert_eq!(
+        vec.len(),
+        original.len(),
+        "expected vector of length {}, got {}",
+        original.len(),
+        vec.len()
+    );
+    let base = batch_grad(mlp, xs, ys);
+
+    let shifted: Vec<f64> = original
+        .iter()
+        .zip(vec.iter())
+        .map(|(w, v)| w + eps * v)
+        .collect();
+    mlp.set_parameters_flat(&shifted);
+    let perturbed = batch_grad(mlp, xs, ys);
+
+    mlp.set_parameters_flat(&original);
+    mlp.zero_grad();
+    perturbed
+        .iter()
+        .zip(base.iter())
+        .map(|(g1, g0)| (g1 - g0) / eps)
+        .collect()
+}
+
+// Dual number a + b*e with e^2 = 0. Evaluating f on Dual::variable(x) carries
+// f'(x) in the eps part exactly, like the complex-step method but without
+// the step size, so it gives an independent check on reverse-mode grads.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dual {
+    pub re: f64,
+    pub eps: f64,
+}
+
+impl Dual {
+    pub fn constant(re: f64) -> Dual {
+        Dual { re, eps: 0.0 }
+    }
+
+    pub fn variable(re: f64) -> Dual {
+        Dual { re, eps: 1.0 }
+    }
+
+    pub fn powf(self, exponent: f64) -> Dual {
+        Dual {
+            re: self.re.powf(exponent),
+            eps: exponent * self.re.powf(exponent - 1.0) * self.eps,
+        }
+    }
+
+    pub fn exp(self) -> Dual {
+        let e = self.re.exp();
+        Dual {
+            re: e,
+            eps: e * self.eps,
+        }
+    }
+
+    pub fn ln(self) -> Dual {
+        Dual {
+            re: self.re.ln(),
+            eps: self.eps / self.re,
+        }
+    }
+
+    pub fn tanh(self) -> Dual {
+        let t = self.re.tanh();
+        Dual {
+            re: t,
+            eps: (1.0 - t * t) * self.eps,
+        }
+    }
+
+    pub fn relu(self) -> Dual {
+        if self.re > 0.0 {
+            self
+        } else {
+            Dual::constant(0.0)
+        }
+    }
+}
+
+impl Add for Dual {
+    type Output = Dual;
+    fn add(self, other: Dual) -> Dual {
+        Dual {
+            re: self.re + other.re,
+            eps: self.eps + other.eps,
+        }
+    }
+}
+
+impl Add<f64> for Dual {
+    type Output = Dual;
+    fn add(self, other: f64) -> Dual {
+        self + Dual::constant(other)
+    }
+}
+
+impl Sub for Dual {
+    type Output = Dual;
+    fn sub(self, other: Dual) -> Dual {
+        Dual {
+            re: self.re - other.re,
+            eps: self.eps - other.eps,
+        }
+    }
+}
+
+impl Sub<f64> for Dual {
+    type Output = Dual;
+    fn sub(self, other: f64) -> Dual {
+        self - Dual::constant(other)
+    }
+}
+
+impl Mul for Dual {
+    type Output = Dual;
+    fn mul(self, other: Dual) -> Dual {
+        Dual {
+            re: self.re * other.re,
+            eps: self.re * other.eps + self.eps * other.re,
+        }
+    }
+}
+
+impl Mul<f64> for Dual {
+    type Output = Dual;
+    fn mul(self, other: f64) -> Dual {
+        self * Dual::constant(other)
+    }
+}
+
+impl Div for Dual {
+    type Output = Dual;
+    fn div(self, other: Dual) -> Dual {
+        Dual {
+            re: self.re / other.re,
+            eps: (self.eps * other.re - self.re * other.eps) / (other.re * other.re),
+        }
+    }
+}
+
+impl Neg for Dual {
+    type Output = Dual;
+    fn neg(self) -> Dual {
+        Dual {
+            re: -self.re,
+            eps: -self.eps,
+        }
+    }
+}
+
+// Five-point central difference of a plain numeric f at x, for comparison
+// against Value::backward; exact for polynomials up to degree 4 apart from
+// rounding. For an exact derivative of a general f, write it over Dual and
+// use dual_grad.
+pub fn central_difference_grad(f: impl Fn(f64) -> f64, x: f64) -> f64 {
+    let h = 1e-3 * x.abs().max(1.0);
+    (f(x - 2.0 * h) - 8.0 * f(x - h) + 8.0 * f(x + h) - f(x + 2.0 * h)) / (12.0 * h)
+}
+
+// Forward-mode derivative of f at x. f is written over Dual rather than f64
+// so the derivative is exact instead of depending on a step size.
+pub fn dual_grad(f: impl Fn(Dual) -> Dual, x: f64) -> f64 {
+    f(Dual::variable(x)).eps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Activation;
+
+    #[test]
+    fn hessian_diagonal_of_powers() {
+        let square = hessian_diagonal(|x| x[0].clone().pow(2.0), &[Value::new(0.7)], 1e-4);
+        assert!((square[0] - 2.0).abs() < 1e-4);
+        let cube = hessian_diagonal(|x| x[0].clone().pow(3.0), &[Value::new(1.0)], 1e-4);
+        assert!((cube[0] - 6.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn central_difference_and_dual_grads_match_backward_on_polynomial() {
+        let x0 = 1.7;
+        // 3x^3 - 2x^2 + x - 5
+        let central = central_difference_grad(|x| 3.0 * x.powi(3) - 2.0 * x * x + x - 5.0, x0);
+        let dual = dual_grad(|x| x * x * x * 3.0 - x * x * 2.0 + x - 5.0, x0);
+
+        let x = Value::new(x0);
+        let y = x.clone().pow(3.0) * 3.0 - x.clone().pow(2.0) * 2.0 + x.clone() - Value::new(5.0);
+        y.backward();
+        assert!((central - x.grad()).abs() < 1e-10);
+        assert!((dual - x.grad()).abs() < 1e-10);
+        assert!((central - (9.0 * x0 * x0 - 4.0 * x0 + 1.0)).abs() < 1e-10);
+    }
+
+    #[test]
+    fn hvp_of_quadratic_loss_is_twice_the_vector() {
+        // With inputs +-1 the mean of (w * x + b - y)^2 is
+        // w^2 + b^2 + (terms linear in w, b), so H = 2 * I.
+        let mlp = MLP::from_weights(
+            vec![vec![vec![0.3]]],
+            vec![vec![-0.2]],
+            vec![Activation::Linear],
+        );
+        let xs = vec![vec![Value::new(1.0)], vec![Value::new(-1.0)]];
+        let ys = vec![Value::new(0.5), Value::new(2.0)];
+        let original = mlp.parameters_flat();
+        let v = [0.7, -1.5];
+        let hv = hvp(&mlp, &xs, &ys, &v, 1e-3);
+        for (h, vi) in hv.iter().zip(v) {
+            assert!((h - 2.0 * vi).abs() < 1e-6);
+        }
+        assert_eq!(mlp.parameters_flat(), original);
+        assert!(mlp.grad_flat().iter().all(|&g| g == 0.0));
+    }
+}
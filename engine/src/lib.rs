@@ -1,21 +1,111 @@
+pub mod adversarial;
+pub mod data;
+pub mod gradcheck;
+mod linalg;
+pub mod loss;
+pub mod metrics;
+pub mod nn;
+pub mod ops;
+pub mod optim;
+pub mod util;
 pub mod value;
+pub mod viz;
+use loss::batch_loss;
+use optim::CyclicLR;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::time::{Duration, Instant};
+use util::kahan_sum;
 use value::Value;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Activation {
+    Relu,
+    Linear,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeError {
+    pub expected: usize,
+    pub got: usize,
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {} inputs, got {}", self.expected, self.got)
+    }
+}
+
+impl std::error::Error for ShapeError {}
+
+// Reported when two networks that must share an architecture don't.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArchitectureError {
+    LayerCount {
+        expected: usize,
+        got: usize,
+    },
+    InputSize {
+        expected: usize,
+        got: usize,
+    },
+    LayerWidth {
+        layer: usize,
+        expected: usize,
+        got: usize,
+    },
+}
+
+impl fmt::Display for ArchitectureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchitectureError::LayerCount { expected, got } => {
+                write!(f, "expected {} layers, got {}", expected, got)
+            }
+            ArchitectureError::InputSize { expected, got } => {
+                write!(f, "expected {} inputs, got {}", expected, got)
+            }
+            ArchitectureError::LayerWidth {
+                layer,
+                expected,
+                got,
+            } => {
+                write!(
+                    f,
+                    "expected layer {} to have {} neurons, got {}",
+                    layer, expected, got
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArchitectureError {}
 
 struct Neuron {
     weights: Vec<Value>,
     bias: Value,
-    activation: bool, // true for ReLU, false for linear
+    activation: Activation,
 }
 
 impl Neuron {
-    fn new(nin: usize, activation: bool) -> Neuron {
+    fn new(nin: usize, activation: Activation, bias_init: f64) -> Neuron {
+        Neuron::with_rng(nin, activation, bias_init, &mut rand::rng())
+    }
+
+    fn with_rng(nin: usize, activation: Activation, bias_init: f64, rng: &mut impl Rng) -> Neuron {
         let mut weights = Vec::with_capacity(nin);
         for _ in 0..nin {
-            weights.push(Value::new(rand::random::<f64>() * 2.0 - 1.0));
+            weights.push(Value::new(rng.random::<f64>() * 2.0 - 1.0));
+        }
+        let bias = Value::new(bias_init);
+        Neuron {
+            weights,
+            bias,
+            activation,
         }
-        let bias = Value::new(0.0);
-        Neuron { weights, bias, activation }
     }
 
     fn forward(&self, x: &[Value]) -> Value {
@@ -23,10 +113,20 @@ impl Neuron {
         for (wi, xi) in self.weights.iter().zip(x.iter()) {
             act = act + wi.clone() * xi.clone();
         }
-        if self.activation {
-            act.relu()
-        } else {
-            act
+        match self.activation {
+            Activation::Relu => act.relu(),
+            Activation::Linear => act,
+        }
+    }
+
+    fn predict(&self, x: &[f64]) -> f64 {
+        let mut act = self.bias.data();
+        for (wi, xi) in self.weights.iter().zip(x.iter()) {
+            act += wi.data() * xi;
+        }
+        match self.activation {
+            Activation::Relu => act.max(0.0),
+            Activation::Linear => act,
         }
     }
 
@@ -42,25 +142,106 @@ impl Neuron {
         params.push(self.bias.clone());
         params
     }
+
+    fn deep_clone(&self) -> Neuron {
+        Neuron {
+            weights: self.weights.iter().map(|w| Value::new(w.data())).collect(),
+            bias: Value::new(self.bias.data()),
+            activation: self.activation,
+        }
+    }
 }
 
-struct Layer {
+pub struct Layer {
     neurons: Vec<Neuron>,
+    // Kept separately so a layer with no neurons still knows its input size
+    // and activation.
+    nin: usize,
+    activation: Activation,
+    // Bias every neuron started from, reused by clone_architecture.
+    bias_init: f64,
 }
 
 impl Layer {
-    fn new(nin: usize, nout: usize, activation: bool) -> Layer {
+    pub fn new(nin: usize, nout: usize, activation: Activation, bias_init: f64) -> Layer {
+        let mut neurons = Vec::with_capacity(nout);
+        for _ in 0..nout {
+            neurons.push(Neuron::new(nin, activation, bias_init));
+        }
+        Layer {
+            neurons,
+            nin,
+            activation,
+            bias_init,
+        }
+    }
+
+    fn with_rng(
+        nin: usize,
+        nout: usize,
+        activation: Activation,
+        bias_init: f64,
+        rng: &mut impl Rng,
+    ) -> Layer {
         let mut neurons = Vec::with_capacity(nout);
         for _ in 0..nout {
-            neurons.push(Neuron::new(nin, activation));
+            neurons.push(Neuron::with_rng(nin, activation, bias_init, rng));
+        }
+        Layer {
+            neurons,
+            nin,
+            activation,
+            bias_init,
+        }
+    }
+
+    fn deep_clone(&self) -> Layer {
+        Layer {
+            neurons: self.neurons.iter().map(|n| n.deep_clone()).collect(),
+            nin: self.nin,
+            activation: self.activation,
+            bias_init: self.bias_init,
         }
-        Layer { neurons }
+    }
+
+    fn nin(&self) -> usize {
+        self.nin
+    }
+
+    // Row j holds the incoming weights of neuron j.
+    fn weight_matrix(&self) -> Vec<Vec<f64>> {
+        self.neurons
+            .iter()
+            .map(|n| n.weights.iter().map(|w| w.data()).collect())
+            .collect()
+    }
+
+    fn activation(&self) -> Activation {
+        self.activation
     }
 
     fn forward(&self, x: &[Value]) -> Vec<Value> {
+        assert_eq!(
+            x.len(),
+            self.nin(),
+            "expected {} inputs, got {}",
+            self.nin(),
+            x.len()
+        );
         self.neurons.iter().map(|n| n.forward(x)).collect()
     }
 
+    fn predict(&self, x: &[f64]) -> Vec<f64> {
+        assert_eq!(
+            x.len(),
+            self.nin(),
+            "expected {} inputs, got {}",
+            self.nin(),
+            x.len()
+        );
+        self.neurons.iter().map(|n| n.predict(x)).collect()
+    }
+
     fn zero_grad(&self) {
         for neuron in &self.neurons {
             neuron.zero_grad();
@@ -76,21 +257,122 @@ impl Layer {
     }
 }
 
+// Per-epoch record returned by MLP::fit_timed. Durations cover the forward,
+// backward and update of the epoch but not the callback. An epoch too short
+// for the clock to register reports 0 samples per second rather than inf.
+#[derive(Debug, Clone)]
+pub struct FitStats {
+    pub losses: Vec<f64>,
+    pub epoch_durations: Vec<Duration>,
+    pub samples_per_sec: Vec<f64>,
+}
+
 pub struct MLP {
     layers: Vec<Layer>,
+    // Dropout rate on hidden outputs for predict_mc_dropout; forward and
+    // predict are deterministic and ignore it.
+    dropout: f64,
 }
 
 impl MLP {
     pub fn new(nin: usize, nouts: &[usize]) -> MLP {
+        MLP::with_bias_init(nin, nouts, 0.0)
+    }
+
+    // Like new, but every bias starts at bias_init instead of 0.0. A small
+    // positive value helps keep ReLU units alive at initialization.
+    pub fn with_bias_init(nin: usize, nouts: &[usize], bias_init: f64) -> MLP {
         let mut layers = Vec::with_capacity(nouts.len());
         let mut in_size = nin;
         for (i, &nout) in nouts.iter().enumerate() {
-            // ReLU for hidden layers, linear (false) for output layer
-            let activation = i < nouts.len() - 1;
-            layers.push(Layer::new(in_size, nout, activation));
+            // ReLU for hidden layers, linear for output layer
+            let activation = if i < nouts.len() - 1 {
+                Activation::Relu
+            } else {
+                Activation::Linear
+            };
+            layers.push(Layer::new(in_size, nout, activation, bias_init));
             in_size = nout;
         }
-        MLP { layers }
+        MLP {
+            layers,
+            dropout: 0.0,
+        }
+    }
+
+    // Sets the dropout rate that predict_mc_dropout applies to every hidden
+    // layer's outputs. The default is 0.0.
+    pub fn with_dropout(mut self, p: f64) -> MLP {
+        assert!(
+            (0.0..1.0).contains(&p),
+            "dropout probability must be in [0, 1), got {}",
+            p
+        );
+        self.dropout = p;
+        self
+    }
+
+    // Builds a network from explicit parameters: weights[l][j] holds the
+    // incoming weights of neuron j in layer l, and biases[l][j] its bias.
+    pub fn from_weights(
+        weights: Vec<Vec<Vec<f64>>>,
+        biases: Vec<Vec<f64>>,
+        activations: Vec<Activation>,
+    ) -> MLP {
+        assert_eq!(
+            weights.len(),
+            biases.len(),
+            "expected one bias vector per layer"
+        );
+        assert_eq!(
+            weights.len(),
+            activations.len(),
+            "expected one activation per layer"
+        );
+        let mut layers = Vec::with_capacity(weights.len());
+        let mut prev_width: Option<usize> = None;
+        for ((layer_weights, layer_biases), activation) in
+            weights.into_iter().zip(biases).zip(activations)
+        {
+            assert_eq!(
+                layer_weights.len(),
+                layer_biases.len(),
+                "expected one bias per neuron"
+            );
+            let nin = layer_weights.first().map_or(0, |row| row.len());
+            assert!(
+                layer_weights.iter().all(|row| row.len() == nin),
+                "expected every neuron in a layer to have {} weights",
+                nin
+            );
+            if let Some(width) = prev_width {
+                assert_eq!(
+                    nin, width,
+                    "expected {} weights per neuron to match the previous layer's width",
+                    width
+                );
+            }
+            prev_width = Some(layer_weights.len());
+            let neurons = layer_weights
+                .into_iter()
+                .zip(layer_biases)
+                .map(|(w, b)| Neuron {
+                    weights: w.into_iter().map(Value::new).collect(),
+                    bias: Value::new(b),
+                    activation,
+                })
+                .collect();
+            layers.push(Layer {
+                neurons,
+                nin,
+                activation,
+                bias_init: 0.0,
+            });
+        }
+        MLP {
+            layers,
+            dropout: 0.0,
+        }
     }
 
     pub fn forward(&self, x: &[Value]) -> Vec<Value> {
@@ -101,6 +383,155 @@ impl MLP {
         out
     }
 
+    // Like forward, but returns a ShapeError instead of panicking when x
+    // doesn't match the network's input size. A network without layers
+    // accepts any input and returns it unchanged, as forward does.
+    pub fn try_forward(&self, x: &[Value]) -> Result<Vec<Value>, ShapeError> {
+        let expected = match self.layers.first() {
+            Some(layer) => layer.nin(),
+            None => return Ok(x.to_vec()),
+        };
+        if x.len() != expected {
+            return Err(ShapeError {
+                expected,
+                got: x.len(),
+            });
+        }
+        Ok(self.forward(x))
+    }
+
+    // Full-batch gradient descent on the MSE of the first output. callback is
+    // invoked after every epoch with (epoch, mean training loss), and the
+    // per-epoch losses are returned.
+    pub fn fit(
+        &self,
+        xs: &[Vec<Value>],
+        ys: &[Value],
+        epochs: usize,
+        learning_rate: f64,
+        callback: impl FnMut(usize, f64),
+    ) -> Vec<f64> {
+        self.fit_timed(xs, ys, epochs, learning_rate, callback)
+            .losses
+    }
+
+    // Same training loop as fit with loss_fn(preds, targets) in place of the
+    // squared error, where preds holds the first output and targets its
+    // target. Passing loss::mse reproduces fit.
+    pub fn fit_with_loss(
+        &self,
+        xs: &[Vec<Value>],
+        ys: &[Value],
+        epochs: usize,
+        learning_rate: f64,
+        loss_fn: impl Fn(&[Value], &[Value]) -> Value,
+    ) -> Vec<f64> {
+        (0..epochs)
+            .map(|_| {
+                self.train_epoch(xs, ys, learning_rate, |pred, y| {
+                    loss_fn(std::slice::from_ref(pred), std::slice::from_ref(y))
+                })
+            })
+            .collect()
+    }
+
+    // Same training loop as fit, also recording how long each epoch took.
+    pub fn fit_timed(
+        &self,
+        xs: &[Vec<Value>],
+        ys: &[Value],
+        epochs: usize,
+        learning_rate: f64,
+        mut callback: impl FnMut(usize, f64),
+    ) -> FitStats {
+        let mut stats = FitStats {
+            losses: Vec::with_capacity(epochs),
+            epoch_durations: Vec::with_capacity(epochs),
+            samples_per_sec: Vec::with_capacity(epochs),
+        };
+        for epoch in 0..epochs {
+            let start = Instant::now();
+            let total_loss = self.train_epoch(xs, ys, learning_rate, |pred, y| {
+                let diff = pred.clone() - y.clone();
+                diff.clone() * diff
+            });
+            let elapsed = start.elapsed();
+
+            callback(epoch, total_loss);
+            stats.losses.push(total_loss);
+            stats.epoch_durations.push(elapsed);
+            let secs = elapsed.as_secs_f64();
+            stats.samples_per_sec.push(if secs > 0.0 {
+                xs.len() as f64 / secs
+            } else {
+                0.0
+            });
+        }
+        stats
+    }
+
+    // One full-batch step shared by the fit variants: accumulates the grads
+    // of loss_fn(first output, target) over every sample, applies a single
+    // update and returns the mean loss.
+    fn train_epoch(
+        &self,
+        xs: &[Vec<Value>],
+        ys: &[Value],
+        learning_rate: f64,
+        loss_fn: impl Fn(&Value, &Value) -> Value,
+    ) -> f64 {
+        self.zero_grad();
+        let mut losses = Vec::with_capacity(xs.len());
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            let loss = loss_fn(&self.forward(x)[0], y);
+            losses.push(loss.data());
+            loss.backward();
+        }
+        for param in self.trainable_parameters() {
+            param.update(learning_rate);
+        }
+        kahan_sum(&losses) / xs.len() as f64
+    }
+
+    // Same result as forward but computed on plain f64s, so no graph is built.
+    pub fn predict(&self, x: &[f64]) -> Vec<f64> {
+        let mut out = x.to_vec();
+        for layer in &self.layers {
+            out = layer.predict(&out);
+        }
+        out
+    }
+
+    // Returns the output of every layer in order; the last entry is the
+    // same as what forward returns.
+    pub fn forward_with_activations(&self, x: &[Value]) -> Vec<Vec<Value>> {
+        let mut activations = Vec::with_capacity(self.layers.len());
+        let mut out = x.to_vec();
+        for layer in &self.layers {
+            out = layer.forward(&out);
+            activations.push(out.clone());
+        }
+        activations
+    }
+
+    // Activation of a single neuron, running only the layers up to and
+    // including the requested one.
+    pub fn neuron_output(&self, layer: usize, neuron: usize, x: &[Value]) -> Value {
+        let mut out = x.to_vec();
+        for l in &self.layers[..layer] {
+            out = l.forward(&out);
+        }
+        let target = &self.layers[layer];
+        assert_eq!(
+            out.len(),
+            target.nin(),
+            "expected {} inputs, got {}",
+            target.nin(),
+            out.len()
+        );
+        target.neurons[neuron].forward(&out)
+    }
+
     pub fn zero_grad(&self) {
         for layer in &self.layers {
             layer.zero_grad();
@@ -114,4 +545,1648 @@ impl MLP {
         }
         params
     }
-}
\ No newline at end of file
+
+    // Connection weights only, excluding biases.
+    fn weights(&self) -> Vec<Value> {
+        let mut weights = Vec::new();
+        for layer in &self.layers {
+            for neuron in &layer.neurons {
+                weights.extend(neuron.weights.iter().cloned());
+            }
+        }
+        weights
+    }
+
+    // Parameter values in parameters() order.
+    pub fn parameters_flat(&self) -> Vec<f64> {
+        self.parameters().iter().map(|p| p.data()).collect()
+    }
+
+    pub fn grad_flat(&self) -> Vec<f64> {
+        self.parameters().iter().map(|p| p.grad()).collect()
+    }
+
+    pub fn set_parameters_flat(&self, values: &[f64]) {
+        let params = self.parameters();
+        assert_eq!(
+            params.len(),
+            values.len(),
+            "expected {} parameter values, got {}",
+            params.len(),
+            values.len()
+        );
+        for (param, &val) in params.iter().zip(values) {
+            param.set_data(val);
+        }
+    }
+
+    // Parameters that haven't been frozen with set_requires_grad(false).
+    pub fn trainable_parameters(&self) -> Vec<Value> {
+        self.parameters()
+            .into_iter()
+            .filter(|p| p.requires_grad())
+            .collect()
+    }
+
+    // Sets every trainable parameter's data to f(data, grad), so
+    // |d, g| d - lr * g is a plain SGD step.
+    pub fn update_custom(&self, f: impl Fn(f64, f64) -> f64) {
+        for param in self.trainable_parameters() {
+            param.set_data(f(param.data(), param.grad()));
+        }
+    }
+
+    // Parameters grouped by layer, in the same order as parameters().
+    pub fn layer_parameters(&self) -> Vec<Vec<Value>> {
+        self.layers.iter().map(|layer| layer.parameters()).collect()
+    }
+
+    // L2 norm of the gradients of each layer's parameters.
+    pub fn layer_grad_norms(&self) -> Vec<f64> {
+        self.layer_parameters()
+            .iter()
+            .map(|params| {
+                params
+                    .iter()
+                    .map(|p| p.grad() * p.grad())
+                    .sum::<f64>()
+                    .sqrt()
+            })
+            .collect()
+    }
+
+    // Mean and max of |lr * grad| over all parameters, without applying the update.
+    pub fn update_stats(&self, lr: f64) -> (f64, f64) {
+        let params = self.parameters();
+        let magnitudes: Vec<f64> = params.iter().map(|p| (lr * p.grad()).abs()).collect();
+        let mean = kahan_sum(&magnitudes) / magnitudes.len() as f64;
+        let max = magnitudes.iter().cloned().fold(0.0, f64::max);
+        (mean, max)
+    }
+
+    // Per layer, ||lr * grad|| / ||w|| over that layer's weights (biases
+    // excluded). Values around 1e-3 are a common sign of a well-tuned rate.
+    // A layer whose weights are all zero reports 0.
+    pub fn update_ratios(&self, lr: f64) -> Vec<f64> {
+        self.layers
+            .iter()
+            .map(|layer| {
+                let weights: Vec<&Value> = layer
+                    .neurons
+                    .iter()
+                    .flat_map(|n| n.weights.iter())
+                    .collect();
+                let update_norm = weights
+                    .iter()
+                    .map(|w| (lr * w.grad()).powi(2))
+                    .sum::<f64>()
+                    .sqrt();
+                let weight_norm = weights.iter().map(|w| w.data().powi(2)).sum::<f64>().sqrt();
+                if weight_norm == 0.0 {
+                    0.0
+                } else {
+                    update_norm / weight_norm
+                }
+            })
+            .collect()
+    }
+
+    // Same layer sizes, activations and initial biases as self, but freshly
+    // initialized weights drawn from a seeded RNG so the result is reproducible.
+    pub fn clone_architecture(&self, seed: u64) -> MLP {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| {
+                Layer::with_rng(
+                    layer.nin(),
+                    layer.neurons.len(),
+                    layer.activation(),
+                    layer.bias_init,
+                    &mut rng,
+                )
+            })
+            .collect();
+        MLP {
+            layers,
+            dropout: self.dropout,
+        }
+    }
+
+    // Copies each weight and bias that exists in both networks, i.e. the
+    // top-left block of every shared layer; anything outside the overlap
+    // keeps its current value.
+    pub fn transfer_from(&self, other: &MLP) {
+        for (layer, src_layer) in self.layers.iter().zip(other.layers.iter()) {
+            for (neuron, src_neuron) in layer.neurons.iter().zip(src_layer.neurons.iter()) {
+                for (w, src_w) in neuron.weights.iter().zip(src_neuron.weights.iter()) {
+                    w.set_data(src_w.data());
+                }
+                neuron.bias.set_data(src_neuron.bias.data());
+            }
+        }
+    }
+
+    // Unlike Value::clone, which shares the underlying Rc, this copies every
+    // parameter into fresh storage so the two networks train independently.
+    pub fn deep_clone(&self) -> MLP {
+        MLP {
+            layers: self.layers.iter().map(|layer| layer.deep_clone()).collect(),
+            dropout: self.dropout,
+        }
+    }
+
+    // Input size followed by each layer's output size; empty for a network
+    // without layers.
+    fn shape(&self) -> Vec<usize> {
+        let mut shape = Vec::with_capacity(self.layers.len() + 1);
+        if let Some(first) = self.layers.first() {
+            shape.push(first.nin());
+        }
+        shape.extend(self.layers.iter().map(|layer| layer.neurons.len()));
+        shape
+    }
+
+    // Layout (all little-endian): number of shape entries as u64, the shape
+    // entries as u64, the parameter count as u64, then each parameter as f64
+    // in parameters() order.
+    pub fn save_bin(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let shape = self.shape();
+        writer.write_all(&(shape.len() as u64).to_le_bytes())?;
+        for dim in shape {
+            writer.write_all(&(dim as u64).to_le_bytes())?;
+        }
+        let params = self.parameters();
+        writer.write_all(&(params.len() as u64).to_le_bytes())?;
+        for param in params {
+            writer.write_all(&param.data().to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    // Loads parameters written by save_bin into this network. The stored
+    // shape must match exactly, otherwise an InvalidData error is returned
+    // and no parameters are modified.
+    pub fn load_bin(&self, path: &str) -> io::Result<()> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut buf = [0u8; 8];
+
+        reader.read_exact(&mut buf)?;
+        let shape_len = u64::from_le_bytes(buf) as usize;
+        let expected = self.shape();
+        if shape_len != expected.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "expected {} shape entries, found {}",
+                    expected.len(),
+                    shape_len
+                ),
+            ));
+        }
+        let mut shape = Vec::with_capacity(shape_len);
+        for _ in 0..shape_len {
+            reader.read_exact(&mut buf)?;
+            shape.push(u64::from_le_bytes(buf) as usize);
+        }
+        if shape != expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("expected shape {:?}, found {:?}", expected, shape),
+            ));
+        }
+
+        let params = self.parameters();
+        reader.read_exact(&mut buf)?;
+        let count = u64::from_le_bytes(buf) as usize;
+        if count != params.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("expected {} parameters, found {}", params.len(), count),
+            ));
+        }
+        let mut values = Vec::with_capacity(count);
+        for _ in 0..count {
+            reader.read_exact(&mut buf)?;
+            values.push(f64::from_le_bytes(buf));
+        }
+
+        for (param, val) in params.iter().zip(values) {
+            param.set_data(val);
+        }
+        Ok(())
+    }
+
+    // Zeroes every weight (biases are left alone) whose magnitude is below
+    // threshold and returns how many were pruned.
+    pub fn prune(&self, threshold: f64) -> usize {
+        let mut pruned = 0;
+        for w in self.weights() {
+            if w.data().abs() < threshold {
+                w.set_data(0.0);
+                pruned += 1;
+            }
+        }
+        pruned
+    }
+
+    // Fraction of weights (biases excluded, as in prune) that are exactly zero;
+    // 0 for a network with no weights.
+    pub fn sparsity(&self) -> f64 {
+        let weights = self.weights();
+        if weights.is_empty() {
+            return 0.0;
+        }
+        let zeros = weights.iter().filter(|w| w.data() == 0.0).count();
+        zeros as f64 / weights.len() as f64
+    }
+
+    // Symmetric per-tensor quantization of every parameter, in parameters()
+    // order: q = round(x / scale) with scale = max|x| / 127.
+    pub fn quantize_int8(&self) -> (Vec<i8>, f64) {
+        let params = self.parameters();
+        let max_abs = params.iter().map(|p| p.data().abs()).fold(0.0, f64::max);
+        let scale = if max_abs > 0.0 { max_abs / 127.0 } else { 1.0 };
+        let data = params
+            .iter()
+            .map(|p| (p.data() / scale).round() as i8)
+            .collect();
+        (data, scale)
+    }
+
+    // Restores parameters from quantize_int8 output as q * scale.
+    pub fn load_quantized(&self, data: &[i8], scale: f64) {
+        let params = self.parameters();
+        assert_eq!(
+            params.len(),
+            data.len(),
+            "expected {} quantized values, got {}",
+            params.len(),
+            data.len()
+        );
+        for (param, &q) in params.iter().zip(data) {
+            param.set_data(q as f64 * scale);
+        }
+    }
+
+    // Gradient of the first output with respect to each weight (biases
+    // excluded) at input x, in layer, neuron, input order. Parameter grads
+    // are zeroed before and after.
+    pub fn weight_sensitivity(&self, x: &[f64]) -> Vec<f64> {
+        let inputs: Vec<Value> = x.iter().map(|&xi| Value::new(xi)).collect();
+        self.zero_grad();
+        self.forward(&inputs)[0].backward();
+        let sensitivity = self.weights().iter().map(|w| w.grad()).collect();
+        self.zero_grad();
+        sensitivity
+    }
+
+    // Monte Carlo dropout: runs the given number of graph-free forward passes,
+    // applying inverted dropout at the rate set by with_dropout to every
+    // hidden layer's outputs, and returns the mean and variance of the first
+    // output.
+    pub fn predict_mc_dropout(&self, x: &[f64], passes: usize, seed: u64) -> (f64, f64) {
+        assert!(passes > 0, "predict_mc_dropout needs at least one pass");
+        let mut rng = StdRng::seed_from_u64(seed);
+        let scale = 1.0 / (1.0 - self.dropout);
+        let mut preds = Vec::with_capacity(passes);
+        for _ in 0..passes {
+            let mut out = x.to_vec();
+            for (i, layer) in self.layers.iter().enumerate() {
+                out = layer.predict(&out);
+                if i + 1 < self.layers.len() && self.dropout > 0.0 {
+                    for o in out.iter_mut() {
+                        *o *= if rng.random::<f64>() >= self.dropout {
+                            scale
+                        } else {
+                            0.0
+                        };
+                    }
+                }
+            }
+            preds.push(out[0]);
+        }
+        // Shifting by the first prediction keeps the variance exactly 0 when
+        // every pass agrees, e.g. with a dropout rate of 0.
+        let n = preds.len() as f64;
+        let shift = preds[0];
+        let mean_offset = preds.iter().map(|y| y - shift).sum::<f64>() / n;
+        let variance = preds
+            .iter()
+            .map(|y| (y - shift - mean_offset).powi(2))
+            .sum::<f64>()
+            / n;
+        (shift + mean_offset, variance)
+    }
+
+    // Rank of the Jacobian of every output with respect to every parameter,
+    // stacked over x and x + h * e_i, i.e. how many parameter directions move
+    // the network's local affine map at x. Parameter grads are zeroed after.
+    pub fn effective_dof(&self, x: &[f64]) -> f64 {
+        let h = 1e-3;
+        let mut probes = vec![x.to_vec()];
+        for i in 0..x.len() {
+            let mut probe = x.to_vec();
+            probe[i] += h;
+            probes.push(probe);
+        }
+        let mut jacobian = Vec::new();
+        for probe in &probes {
+            let inputs: Vec<Value> = probe.iter().map(|&xi| Value::new(xi)).collect();
+            for output in 0..self.forward(&inputs).len() {
+                self.zero_grad();
+                self.forward(&inputs)[output].backward();
+                jacobian.push(self.grad_flat());
+            }
+        }
+        self.zero_grad();
+        linalg::rank(&jacobian) as f64
+    }
+
+    // Attribution of the first output to each input: (x - baseline) times the
+    // average gradient along the straight path from baseline to x, using the
+    // midpoint rule with the given number of steps. Parameter grads are
+    // zeroed afterwards.
+    pub fn integrated_gradients(&self, x: &[f64], baseline: &[f64], steps: usize) -> Vec<f64> {
+        assert_eq!(
+            x.len(),
+            baseline.len(),
+            "expected baseline of length {}, got {}",
+            x.len(),
+            baseline.len()
+        );
+        assert!(steps > 0, "integrated_gradients needs at least one step");
+        let mut total = vec![0.0; x.len()];
+        for k in 0..steps {
+            let alpha = (k as f64 + 0.5) / steps as f64;
+            let inputs: Vec<Value> = x
+                .iter()
+                .zip(baseline.iter())
+                .map(|(&xi, &bi)| Value::new(bi + alpha * (xi - bi)))
+                .collect();
+            self.forward(&inputs)[0].backward();
+            for (t, input) in total.iter_mut().zip(inputs.iter()) {
+                *t += input.grad();
+            }
+        }
+        self.zero_grad();
+        total
+            .iter()
+            .zip(x.iter().zip(baseline.iter()))
+            .map(|(g, (&xi, &bi))| (xi - bi) * g / steps as f64)
+            .collect()
+    }
+
+    // Forward pass in fixed point with frac_bits fractional bits: inputs,
+    // weights and outputs are scaled by 2^frac_bits. Weights are rounded to
+    // that grid, products accumulate in i128 and are rounded back to the
+    // working precision, and each layer's outputs saturate at the i32 range.
+    // frac_bits must be below 31 so 1.0 is representable in an i32.
+    pub fn predict_fixed(&self, x: &[i32], frac_bits: u32) -> Vec<i32> {
+        assert!(
+            frac_bits < 31,
+            "frac_bits must be below 31, got {}",
+            frac_bits
+        );
+        let one = (1i64 << frac_bits) as f64;
+        let half = if frac_bits > 0 {
+            1i128 << (frac_bits - 1)
+        } else {
+            0
+        };
+        let to_fixed = |v: f64| (v * one).round() as i64;
+        let mut out: Vec<i64> = x.iter().map(|&xi| xi as i64).collect();
+        for layer in &self.layers {
+            assert_eq!(
+                out.len(),
+                layer.nin(),
+                "expected {} inputs, got {}",
+                layer.nin(),
+                out.len()
+            );
+            out = layer
+                .neurons
+                .iter()
+                .map(|n| {
+                    let acc: i128 = n
+                        .weights
+                        .iter()
+                        .zip(out.iter())
+                        .map(|(w, &xi)| to_fixed(w.data()) as i128 * xi as i128)
+                        .sum();
+                    let act = ((acc + half) >> frac_bits) + to_fixed(n.bias.data()) as i128;
+                    let act = act.clamp(i32::MIN as i128, i32::MAX as i128) as i64;
+                    match n.activation {
+                        Activation::Relu => act.max(0),
+                        Activation::Linear => act,
+                    }
+                })
+                .collect();
+        }
+        out.into_iter().map(|v| v as i32).collect()
+    }
+
+    // Average wall-clock time of one forward pass plus backward from the
+    // first output. Parameter grads are zeroed afterwards.
+    pub fn benchmark(&self, x: &[f64], iterations: usize) -> Duration {
+        if iterations == 0 {
+            return Duration::ZERO;
+        }
+        let start = Instant::now();
+        for _ in 0..iterations {
+            let inputs: Vec<Value> = x.iter().map(|&xi| Value::new(xi)).collect();
+            self.forward(&inputs)[0].backward();
+        }
+        let elapsed = start.elapsed();
+        self.zero_grad();
+        elapsed.div_f64(iterations as f64)
+    }
+
+    // Evaluates the first output of a two-input network over a
+    // resolution x resolution grid. Row i corresponds to the i-th y value and
+    // column j to the j-th x value, both spaced evenly over their ranges.
+    pub fn grid_eval(
+        &self,
+        x_range: (f64, f64),
+        y_range: (f64, f64),
+        resolution: usize,
+    ) -> Vec<Vec<f64>> {
+        let coord = |range: (f64, f64), i: usize| {
+            if resolution > 1 {
+                range.0 + (range.1 - range.0) * i as f64 / (resolution - 1) as f64
+            } else {
+                range.0
+            }
+        };
+        (0..resolution)
+            .map(|i| {
+                let y = coord(y_range, i);
+                (0..resolution)
+                    .map(|j| {
+                        let x = coord(x_range, j);
+                        self.forward(&[Value::new(x), Value::new(y)])[0].data()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Sum over all paths from input_index to output_index of the product of
+    // weights along each path, with each ReLU on or off as it is at input x,
+    // i.e. entry (output, input) of W_L * D_{L-1} * ... * D_1 * W_1 where D_l
+    // masks the units inactive at x. Biases are ignored.
+    pub fn effective_weight(&self, x: &[f64], output_index: usize, input_index: usize) -> f64 {
+        let mut contrib = vec![0.0; x.len()];
+        contrib[input_index] = 1.0;
+        let mut out = x.to_vec();
+        for layer in &self.layers {
+            out = layer.predict(&out);
+            contrib = layer
+                .weight_matrix()
+                .iter()
+                .zip(layer.neurons.iter().zip(out.iter()))
+                .map(|(row, (n, &o))| {
+                    if n.activation == Activation::Linear || o > 0.0 {
+                        row.iter().zip(contrib.iter()).map(|(w, c)| w * c).sum()
+                    } else {
+                        0.0
+                    }
+                })
+                .collect();
+        }
+        contrib[output_index]
+    }
+
+    // Snapshot ensembling: trains with full-batch MSE steps whose rate follows
+    // schedule for the given number of cycles, saving parameters_flat at the
+    // end of each cycle, where the rate is at its minimum.
+    pub fn collect_snapshots(
+        &self,
+        xs: &[Vec<Value>],
+        ys: &[Value],
+        schedule: &CyclicLR,
+        cycles: usize,
+    ) -> Vec<Vec<f64>> {
+        let period = schedule.cycle_length();
+        let mut snapshots = Vec::with_capacity(cycles);
+        for step in 1..=cycles * period {
+            self.zero_grad();
+            batch_loss(self, xs, ys).backward();
+            for param in self.trainable_parameters() {
+                param.update(schedule.lr(step));
+            }
+            if step % period == 0 {
+                snapshots.push(self.parameters_flat());
+            }
+        }
+        snapshots
+    }
+
+    // Averages the outputs obtained by loading each snapshot in turn. The
+    // network's own parameters are restored afterwards.
+    pub fn ensemble_predict(&self, snapshots: &[Vec<f64>], x: &[f64]) -> Vec<f64> {
+        let original = self.parameters_flat();
+        let inputs: Vec<Value> = x.iter().map(|&xi| Value::new(xi)).collect();
+        let mut sum: Vec<f64> = Vec::new();
+        for snapshot in snapshots {
+            self.set_parameters_flat(snapshot);
+            let out = self.forward(&inputs);
+            if sum.is_empty() {
+                sum = vec![0.0; out.len()];
+            }
+            for (s, o) in sum.iter_mut().zip(out.iter()) {
+                *s += o.data();
+            }
+        }
+        self.set_parameters_flat(&original);
+        sum.iter().map(|s| s / snapshots.len() as f64).collect()
+    }
+
+    // Emits a standalone `fn(&[f64]) -> Vec<f64>` that computes the same
+    // forward pass with the current parameters baked in as literals.
+    pub fn to_rust_fn(&self, fn_name: &str) -> String {
+        fn literal(v: f64) -> String {
+            if v.is_nan() {
+                "f64::NAN".to_string()
+            } else if v.is_infinite() {
+                if v > 0.0 {
+                    "f64::INFINITY".to_string()
+                } else {
+                    "f64::NEG_INFINITY".to_string()
+                }
+            } else {
+                format!("{:?}", v)
+            }
+        }
+
+        let mut code = format!("fn {}(x: &[f64]) -> Vec<f64> {{\n", fn_name);
+        let mut input = "x".to_string();
+        for (l, layer) in self.layers.iter().enumerate() {
+            code.push_str(&format!("    let l{} = [\n", l));
+            for neuron in &layer.neurons {
+                let mut expr = literal(neuron.bias.data());
+                for (i, w) in neuron.weights.iter().enumerate() {
+                    expr.push_str(&format!(" + {} * {}[{}]", literal(w.data()), input, i));
+                }
+                if neuron.activation == Activation::Relu {
+                    expr = format!("({}).max(0.0)", expr);
+                }
+                code.push_str(&format!("        {},\n", expr));
+            }
+            code.push_str("    ];\n");
+            input = format!("l{}", l);
+        }
+        code.push_str(&format!("    {}.to_vec()\n}}\n", input));
+        code
+    }
+
+    // Estimate of the largest singular value of a layer's weight matrix
+    // (biases excluded), via power iteration on the raw weights.
+    pub fn spectral_norm(&self, layer: usize, iterations: usize) -> f64 {
+        linalg::spectral_norm(&self.layers[layer].weight_matrix(), iterations)
+    }
+
+    // Ratio of the largest to smallest singular value of a layer's weights;
+    // infinite when the smallest is zero.
+    pub fn condition_number(&self, layer: usize, iterations: usize) -> f64 {
+        let weights = self.layers[layer].weight_matrix();
+        let sigma_min = linalg::min_singular_value(&weights, iterations);
+        if sigma_min == 0.0 {
+            return f64::INFINITY;
+        }
+        linalg::spectral_norm(&weights, iterations) / sigma_min
+    }
+
+    // Divides a layer's weights (not its biases) by their estimated spectral
+    // norm so the linear map becomes 1-Lipschitz. A zero matrix is left as is.
+    pub fn spectral_normalize(&self, layer: usize, iterations: usize) {
+        let sigma = self.spectral_norm(layer, iterations);
+        if sigma == 0.0 {
+            return;
+        }
+        for neuron in &self.layers[layer].neurons {
+            for w in &neuron.weights {
+                w.set_data(w.data() / sigma);
+            }
+        }
+    }
+
+    // Upper bound on the network's Lipschitz constant in the L2 norm: the
+    // product of each layer's spectral norm, since ReLU is 1-Lipschitz.
+    pub fn lipschitz_bound(&self, iterations: usize) -> f64 {
+        (0..self.layers.len())
+            .map(|i| self.spectral_norm(i, iterations))
+            .product()
+    }
+
+    // New network with parameters alpha * self + (1 - alpha) * other and
+    // self's activations. Both networks must have the same layer sizes; on a
+    // mismatch the layer counts, or else the first differing size, is
+    // reported.
+    pub fn interpolate(&self, other: &MLP, alpha: f64) -> Result<MLP, ArchitectureError> {
+        if self.layers.len() != other.layers.len() {
+            return Err(ArchitectureError::LayerCount {
+                expected: self.layers.len(),
+                got: other.layers.len(),
+            });
+        }
+        let (shape, other_shape) = (self.shape(), other.shape());
+        if let Some((i, (&expected, &got))) = shape
+            .iter()
+            .zip(other_shape.iter())
+            .enumerate()
+            .find(|(_, (a, b))| a != b)
+        {
+            return Err(if i == 0 {
+                ArchitectureError::InputSize { expected, got }
+            } else {
+                ArchitectureError::LayerWidth {
+                    layer: i - 1,
+                    expected,
+                    got,
+                }
+            });
+        }
+
+        let mixed = self.deep_clone();
+        for ((p, a), b) in mixed
+            .parameters()
+            .iter()
+            .zip(self.parameters())
+            .zip(other.parameters())
+        {
+            p.set_data(alpha * a.data() + (1.0 - alpha) * b.data());
+        }
+        Ok(mixed)
+    }
+
+    // 2 * nin * nout multiply-adds per layer, plus one op per ReLU activation.
+    pub fn flops(&self) -> usize {
+        self.layers
+            .iter()
+            .map(|layer| {
+                let nout = layer.neurons.len();
+                let activations = if layer.activation() == Activation::Relu {
+                    nout
+                } else {
+                    0
+                };
+                2 * layer.nin() * nout + activations
+            })
+            .sum()
+    }
+
+    // Counts of weight values (biases excluded) in `bins` equal-width bins
+    // spanning the smallest to the largest weight.
+    pub fn weight_histogram(&self, bins: usize) -> Vec<usize> {
+        let mut counts = vec![0; bins];
+        let values: Vec<f64> = self.weights().iter().map(|w| w.data()).collect();
+        if bins == 0 || values.is_empty() {
+            return counts;
+        }
+        let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let width = (max - min) / bins as f64;
+        for v in values {
+            let bin = if width > 0.0 {
+                ((v - min) / width) as usize
+            } else {
+                0
+            };
+            counts[bin.min(bins - 1)] += 1;
+        }
+        counts
+    }
+}
+
+// Weighted sum of each model's outputs, built in the graph so gradients
+// reach every model's parameters.
+pub fn ensemble(models: &[&MLP], weights: &[f64], x: &[Value]) -> Vec<Value> {
+    assert_eq!(models.len(), weights.len(), "expected one weight per model");
+    let mut out: Vec<Value> = Vec::new();
+    for (model, &w) in models.iter().zip(weights.iter()) {
+        let preds = model.forward(x);
+        if out.is_empty() {
+            out = preds.into_iter().map(|p| p * w).collect();
+        } else {
+            out = out.into_iter().zip(preds).map(|(o, p)| o + p * w).collect();
+        }
+    }
+    out
+}
+
+// Stacks two networks so the output of first feeds second. Each layer keeps
+// its own activation, so first's linear output layer stays linear. A network
+// without layers passes its input through, so chaining it changes nothing.
+// Both networks must use the same dropout rate, which the result keeps.
+pub fn chain(first: MLP, second: MLP) -> MLP {
+    assert_eq!(
+        first.dropout, second.dropout,
+        "cannot chain networks with dropout rates {} and {}",
+        first.dropout, second.dropout
+    );
+    if let (Some(last), Some(next)) = (first.layers.last(), second.layers.first()) {
+        let (first_out, second_in) = (last.neurons.len(), next.nin());
+        assert_eq!(
+            first_out, second_in,
+            "cannot chain a network with {} outputs into one expecting {} inputs",
+            first_out, second_in
+        );
+    }
+    let mut layers = first.layers;
+    layers.extend(second.layers);
+    MLP {
+        layers,
+        dropout: first.dropout,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // y = x1 + x2 on a few points.
+    fn sum_dataset() -> (Vec<Vec<Value>>, Vec<Value>) {
+        let points = [
+            [0.0, 0.0],
+            [0.0, 1.0],
+            [1.0, 0.0],
+            [1.0, 1.0],
+            [0.5, 0.5],
+            [0.2, 0.3],
+        ];
+        let xs = points.iter().map(|p| inputs(p)).collect();
+        let ys = points.iter().map(|p| Value::new(p[0] + p[1])).collect();
+        (xs, ys)
+    }
+
+    fn inputs(xs: &[f64]) -> Vec<Value> {
+        xs.iter().map(|&x| Value::new(x)).collect()
+    }
+
+    #[test]
+    fn clone_architecture_reinitializes_weights() {
+        let mlp = MLP::new(3, &[4, 2]);
+        let clone = mlp.clone_architecture(42);
+        assert_eq!(clone.parameters().len(), mlp.parameters().len());
+        let data = |m: &MLP| m.parameters().iter().map(|p| p.data()).collect::<Vec<_>>();
+        assert_ne!(data(&clone), data(&mlp));
+        // Same seed, same weights.
+        assert_eq!(data(&clone), data(&mlp.clone_architecture(42)));
+
+        let biased = MLP::with_bias_init(3, &[4, 2], 0.25).clone_architecture(7);
+        for layer in &biased.layers {
+            assert!(layer.neurons.iter().all(|n| n.bias.data() == 0.25));
+        }
+    }
+
+    #[test]
+    fn deep_clone_is_independent() {
+        let mlp = MLP::new(2, &[3, 1]);
+        let copy = mlp.deep_clone();
+        let data = |m: &MLP| m.parameters().iter().map(|p| p.data()).collect::<Vec<_>>();
+        assert_eq!(data(&copy), data(&mlp));
+
+        copy.forward(&inputs(&[0.5, -0.5]))[0].backward();
+        for p in copy.parameters() {
+            p.set_data(p.data() + 1.0);
+        }
+        assert!(mlp.parameters().iter().all(|p| p.grad() == 0.0));
+        assert!(data(&mlp).iter().zip(data(&copy)).all(|(a, b)| a != &b));
+    }
+
+    #[test]
+    fn forward_with_activations_returns_each_layer() {
+        let mlp = MLP::new(2, &[5, 3, 1]);
+        let x = inputs(&[0.2, 0.9]);
+        let activations = mlp.forward_with_activations(&x);
+        let widths: Vec<usize> = activations.iter().map(|a| a.len()).collect();
+        assert_eq!(widths, vec![5, 3, 1]);
+        assert_eq!(activations[2][0].data(), mlp.forward(&x)[0].data());
+    }
+
+    #[test]
+    fn layer_grad_norms_has_one_entry_per_layer() {
+        let mlp = MLP::new(2, &[4, 3, 1]);
+        mlp.forward(&inputs(&[1.0, -1.0]))[0].backward();
+        let norms = mlp.layer_grad_norms();
+        assert_eq!(norms.len(), 3);
+        assert!(norms.iter().all(|&n| n >= 0.0));
+    }
+
+    #[test]
+    fn save_bin_round_trip_and_shape_mismatch() {
+        let path = std::env::temp_dir().join(format!("engine_{}_mlp.bin", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mlp = MLP::new(2, &[3, 1]);
+        mlp.save_bin(path).unwrap();
+
+        let restored = MLP::new(2, &[3, 1]);
+        restored.load_bin(path).unwrap();
+        let x = inputs(&[0.3, -0.7]);
+        assert_eq!(restored.forward(&x)[0].data(), mlp.forward(&x)[0].data());
+
+        let other = MLP::new(2, &[4, 1]);
+        let err = other.load_bin(path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn from_weights_reproduces_original() {
+        let mlp = MLP::new(3, &[4, 2]);
+        let weights: Vec<Vec<Vec<f64>>> = mlp
+            .layers
+            .iter()
+            .map(|l| {
+                l.neurons
+                    .iter()
+                    .map(|n| n.weights.iter().map(|w| w.data()).collect())
+                    .collect()
+            })
+            .collect();
+        let biases: Vec<Vec<f64>> = mlp
+            .layers
+            .iter()
+            .map(|l| l.neurons.iter().map(|n| n.bias.data()).collect())
+            .collect();
+        let rebuilt =
+            MLP::from_weights(weights, biases, vec![Activation::Relu, Activation::Linear]);
+        let x = inputs(&[0.3, -0.8, 1.5]);
+        let expected: Vec<f64> = mlp.forward(&x).iter().map(|v| v.data()).collect();
+        let got: Vec<f64> = rebuilt.forward(&x).iter().map(|v| v.data()).collect();
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected every neuron in a layer to have 2 weights")]
+    fn from_weights_rejects_ragged_rows() {
+        MLP::from_weights(
+            vec![vec![vec![1.0, 2.0], vec![3.0]]],
+            vec![vec![0.0, 0.0]],
+            vec![Activation::Linear],
+        );
+    }
+
+    #[test]
+    fn prune_zeroes_exactly_the_small_weights() {
+        let mlp = MLP::from_weights(
+            vec![vec![vec![0.05, -0.5, 0.2], vec![-0.01, 0.9, -0.15]]],
+            vec![vec![0.001, 0.0]],
+            vec![Activation::Linear],
+        );
+        assert_eq!(mlp.prune(0.2), 3);
+        assert_eq!(
+            mlp.weights().iter().map(|w| w.data()).collect::<Vec<_>>(),
+            vec![0.0, -0.5, 0.2, 0.0, 0.9, 0.0]
+        );
+        // Biases are not pruned.
+        assert_eq!(mlp.layers[0].neurons[0].bias.data(), 0.001);
+    }
+
+    #[test]
+    fn quantize_int8_round_trip_within_half_step() {
+        let mlp = MLP::new(3, &[5, 2]);
+        let original = mlp.parameters_flat();
+        let x = [0.5, -1.0, 2.0];
+        let before = mlp.predict(&x);
+        let (data, scale) = mlp.quantize_int8();
+        mlp.load_quantized(&data, scale);
+        for (a, b) in original.iter().zip(mlp.parameters_flat()) {
+            assert!((a - b).abs() <= scale / 2.0 + 1e-12);
+        }
+
+        // Every parameter moves by at most step = scale / 2 and has magnitude
+        // at most max = 127 * scale. A hidden ReLU unit then moves by at most
+        // step * (|x|_1 + 1) and has magnitude at most max * (|x|_1 + 1); each
+        // output sums 5 such units through weights of magnitude max + step,
+        // plus the change in its own weights and bias.
+        let step = scale / 2.0;
+        let max = 127.0 * scale;
+        let x_norm = x.iter().map(|v| v.abs()).sum::<f64>() + 1.0;
+        let hidden_err = step * x_norm;
+        let bound = 5.0 * (max + step) * hidden_err + step * (5.0 * max * x_norm + 1.0);
+        for (a, b) in before.iter().zip(mlp.predict(&x)) {
+            assert!((a - b).abs() <= bound, "{} vs {} exceeds {}", a, b, bound);
+        }
+    }
+
+    #[test]
+    fn fit_calls_back_once_per_epoch() {
+        let (xs, ys) = sum_dataset();
+        let mlp = MLP::new(2, &[4, 1]);
+        let mut epochs_seen = Vec::new();
+        let history = mlp.fit(&xs, &ys, 7, 0.01, |epoch, loss| {
+            epochs_seen.push((epoch, loss))
+        });
+        assert_eq!(
+            epochs_seen.iter().map(|e| e.0).collect::<Vec<_>>(),
+            (0..7).collect::<Vec<_>>()
+        );
+        assert_eq!(epochs_seen.iter().map(|e| e.1).collect::<Vec<_>>(), history);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 3 inputs, got 2")]
+    fn layer_forward_rejects_wrong_input_size() {
+        Layer::new(3, 2, Activation::Relu, 0.0).forward(&inputs(&[1.0, 2.0]));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 3 inputs, got 2")]
+    fn empty_layer_still_checks_input_size() {
+        Layer::new(3, 0, Activation::Relu, 0.0).forward(&inputs(&[1.0, 2.0]));
+    }
+
+    #[test]
+    fn try_forward_reports_sizes() {
+        let mlp = MLP::new(3, &[2, 1]);
+        match mlp.try_forward(&inputs(&[1.0])) {
+            Err(err) => assert_eq!(
+                err,
+                ShapeError {
+                    expected: 3,
+                    got: 1
+                }
+            ),
+            Ok(_) => panic!("expected a shape error"),
+        }
+        assert!(mlp.try_forward(&inputs(&[1.0, 2.0, 3.0])).is_ok());
+
+        let empty = MLP::new(3, &[]);
+        let out = empty.try_forward(&inputs(&[1.0, 2.0])).unwrap();
+        assert_eq!(
+            out.iter().map(|v| v.data()).collect::<Vec<_>>(),
+            vec![1.0, 2.0]
+        );
+        assert!(empty.shape().is_empty());
+    }
+
+    #[test]
+    fn update_stats_from_known_grads() {
+        let mlp = MLP::from_weights(
+            vec![vec![vec![0.5]]],
+            vec![vec![0.0]],
+            vec![Activation::Linear],
+        );
+        // d/dw (-3 * (w * 2 + b)) = -6 and d/db = -3.
+        (mlp.forward(&inputs(&[2.0]))[0].clone() * -3.0).backward();
+        let (mean, max) = mlp.update_stats(0.5);
+        assert_eq!(mean, 2.25);
+        assert_eq!(max, 3.0);
+    }
+
+    #[test]
+    fn with_bias_init_sets_every_bias() {
+        let mlp = MLP::with_bias_init(2, &[4, 3, 1], 0.25);
+        for layer in &mlp.layers {
+            assert!(layer.neurons.iter().all(|n| n.bias.data() == 0.25));
+        }
+    }
+
+    #[test]
+    fn neuron_output_matches_layer_activations() {
+        let mlp = MLP::new(2, &[3, 2, 1]);
+        let x = inputs(&[0.4, -1.1]);
+        let activations = mlp.forward_with_activations(&x);
+        for (l, layer) in activations.iter().enumerate() {
+            for (n, value) in layer.iter().enumerate() {
+                assert_eq!(mlp.neuron_output(l, n, &x).data(), value.data());
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 2 inputs, got 3")]
+    fn neuron_output_rejects_wrong_input_size() {
+        MLP::new(2, &[3, 1]).neuron_output(0, 1, &inputs(&[0.4, -1.1, 0.2]));
+    }
+
+    #[test]
+    fn benchmark_reports_positive_duration() {
+        let mlp = MLP::new(4, &[16, 16, 1]);
+        let per_iter = mlp.benchmark(&[0.1, 0.2, 0.3, 0.4], 5);
+        assert!(per_iter > Duration::ZERO);
+        assert!(per_iter < Duration::from_secs(1));
+        assert_eq!(mlp.benchmark(&[0.1, 0.2, 0.3, 0.4], 0), Duration::ZERO);
+        assert!(mlp.parameters().iter().all(|p| p.grad() == 0.0));
+    }
+
+    #[test]
+    fn grid_eval_rows_follow_y_and_columns_follow_x() {
+        // f(x, y) = x + 2y + 0.5
+        let mlp = MLP::from_weights(
+            vec![vec![vec![1.0, 2.0]]],
+            vec![vec![0.5]],
+            vec![Activation::Linear],
+        );
+        let grid = mlp.grid_eval((0.0, 1.0), (-1.0, 1.0), 3);
+        assert_eq!(grid.len(), 3);
+        assert!(grid.iter().all(|row| row.len() == 3));
+        for (i, y) in [-1.0, 0.0, 1.0].iter().enumerate() {
+            for (j, x) in [0.0, 0.5, 1.0].iter().enumerate() {
+                assert!((grid[i][j] - (x + 2.0 * y + 0.5)).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn effective_weight_of_linear_net_is_matrix_product() {
+        // W2 * W1 = [[1, 2]] * [[1, 2], [3, 4]] = [[7, 10]]
+        let mlp = MLP::from_weights(
+            vec![vec![vec![1.0, 2.0], vec![3.0, 4.0]], vec![vec![1.0, 2.0]]],
+            vec![vec![0.5, -0.5], vec![1.0]],
+            vec![Activation::Linear, Activation::Linear],
+        );
+        assert_eq!(mlp.effective_weight(&[0.3, -0.2], 0, 0), 7.0);
+        assert_eq!(mlp.effective_weight(&[0.3, -0.2], 0, 1), 10.0);
+    }
+
+    #[test]
+    fn effective_weight_drops_units_off_at_input() {
+        // Hidden unit 0 computes x1 - x2 and unit 1 computes x1 + x2.
+        let mlp = MLP::from_weights(
+            vec![vec![vec![1.0, -1.0], vec![1.0, 1.0]], vec![vec![2.0, 3.0]]],
+            vec![vec![0.0, 0.0], vec![0.0]],
+            vec![Activation::Relu, Activation::Linear],
+        );
+        // Both units on: [[2, 3]] * [[1, -1], [1, 1]] = [[5, 1]].
+        assert_eq!(mlp.effective_weight(&[2.0, 1.0], 0, 0), 5.0);
+        assert_eq!(mlp.effective_weight(&[2.0, 1.0], 0, 1), 1.0);
+        // Unit 0 is off at (1, 2), leaving only the path through unit 1.
+        assert_eq!(mlp.effective_weight(&[1.0, 2.0], 0, 0), 3.0);
+        assert_eq!(mlp.effective_weight(&[1.0, 2.0], 0, 1), 3.0);
+    }
+
+    #[test]
+    fn collect_snapshots_saves_once_per_cycle_at_the_lowest_rate() {
+        let linear = || {
+            MLP::from_weights(
+                vec![vec![vec![0.5]]],
+                vec![vec![0.0]],
+                vec![Activation::Linear],
+            )
+        };
+        let xs = vec![inputs(&[1.0]), inputs(&[-2.0])];
+        let ys = inputs(&[3.0, -3.0]);
+        let schedule = CyclicLR::new(0.01, 0.05, 2);
+        let snapshots = linear().collect_snapshots(&xs, &ys, &schedule, 3);
+        assert_eq!(snapshots.len(), 3);
+
+        // Replay the same steps and keep the parameters wherever the rate
+        // bottoms out at base_lr.
+        let replay = linear();
+        let mut at_minimum = Vec::new();
+        for step in 1..=3 * schedule.cycle_length() {
+            replay.zero_grad();
+            batch_loss(&replay, &xs, &ys).backward();
+            for param in replay.parameters() {
+                param.update(schedule.lr(step));
+            }
+            if schedule.lr(step) == 0.01 {
+                at_minimum.push(replay.parameters_flat());
+            }
+        }
+        assert_eq!(snapshots, at_minimum);
+    }
+
+    #[test]
+    fn ensemble_predict_averages_snapshots() {
+        let mlp = MLP::from_weights(
+            vec![vec![vec![1.0, 0.0]]],
+            vec![vec![0.0]],
+            vec![Activation::Linear],
+        );
+        let original = mlp.parameters_flat();
+        // Snapshots are [w1, w2, b]: 2*x1 + 1 and x2 - 1.
+        let snapshots = vec![vec![2.0, 0.0, 1.0], vec![0.0, 1.0, -1.0]];
+        let out = mlp.ensemble_predict(&snapshots, &[3.0, 5.0]);
+        assert_eq!(out, vec![(7.0 + 4.0) / 2.0]);
+        assert_eq!(mlp.parameters_flat(), original);
+    }
+
+    #[test]
+    fn to_rust_fn_emits_forward_pass_with_literal_weights() {
+        let mlp = MLP::from_weights(
+            vec![vec![vec![1.0, -2.0]], vec![vec![3.0]]],
+            vec![vec![0.5], vec![0.0]],
+            vec![Activation::Relu, Activation::Linear],
+        );
+        let expected = "fn net(x: &[f64]) -> Vec<f64> {\n\
+                        \x20   let l0 = [\n\
+                        \x20       (0.5 + 1.0 * x[0] + -2.0 * x[1]).max(0.0),\n\
+                        \x20   ];\n\
+                        \x20   let l1 = [\n\
+                        \x20       0.0 + 3.0 * l0[0],\n\
+                        \x20   ];\n\
+                        \x20   l1.to_vec()\n\
+                        }\n";
+        assert_eq!(mlp.to_rust_fn("net"), expected);
+
+        // The same expression, evaluated by hand, matches forward.
+        let net = |x: &[f64]| vec![0.0 + 3.0 * (0.5 + 1.0 * x[0] + -2.0 * x[1]).max(0.0)];
+        for x in [[1.0, 0.1], [0.0, 1.0]] {
+            assert_eq!(net(&x), vec![mlp.forward(&inputs(&x))[0].data()]);
+        }
+    }
+
+    #[test]
+    fn spectral_norm_of_diagonal_is_largest_entry() {
+        let mlp = MLP::from_weights(
+            vec![vec![vec![3.0, 0.0], vec![0.0, -5.0]]],
+            vec![vec![1.0, 1.0]],
+            vec![Activation::Linear],
+        );
+        assert!((mlp.spectral_norm(0, 50) - 5.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn spectral_norm_finds_direction_orthogonal_to_ones() {
+        // [[1, -1]] has singular value sqrt(2) along (1, -1) / sqrt(2).
+        let mlp = MLP::from_weights(
+            vec![vec![vec![1.0, -1.0]]],
+            vec![vec![0.0]],
+            vec![Activation::Linear],
+        );
+        assert!((mlp.spectral_norm(0, 50) - 2f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn spectral_normalize_brings_norm_to_one() {
+        // diag(3, -5) has norm 5, so it becomes diag(0.6, -1).
+        let diag = MLP::from_weights(
+            vec![vec![vec![3.0, 0.0], vec![0.0, -5.0]]],
+            vec![vec![1.0, 1.0]],
+            vec![Activation::Linear],
+        );
+        diag.spectral_normalize(0, 100);
+        let expected = [0.6, 0.0, 1.0, 0.0, -1.0, 1.0];
+        for (p, e) in diag.parameters_flat().iter().zip(expected) {
+            assert!((p - e).abs() < 1e-9);
+        }
+        // [[1, -1]] has norm sqrt(2) along a direction orthogonal to ones.
+        let row = MLP::from_weights(
+            vec![vec![vec![1.0, -1.0]]],
+            vec![vec![0.0]],
+            vec![Activation::Linear],
+        );
+        row.spectral_normalize(0, 100);
+        let half = 0.5f64.sqrt();
+        for (p, e) in row.parameters_flat().iter().zip([half, -half, 0.0]) {
+            assert!((p - e).abs() < 1e-9);
+        }
+        // A zero matrix is left as is.
+        let zero = MLP::from_weights(
+            vec![vec![vec![0.0, 0.0]]],
+            vec![vec![2.0]],
+            vec![Activation::Linear],
+        );
+        zero.spectral_normalize(0, 10);
+        assert_eq!(zero.parameters_flat(), vec![0.0, 0.0, 2.0]);
+    }
+
+    #[test]
+    fn update_ratios_from_known_grads_and_weights() {
+        let mlp = MLP::from_weights(
+            vec![vec![vec![3.0, 4.0]], vec![vec![0.0]]],
+            vec![vec![10.0], vec![1.0]],
+            vec![Activation::Linear, Activation::Linear],
+        );
+        let weights: Vec<Value> = mlp.layers[0].neurons[0].weights.clone();
+        (weights[0].clone() * 6.0 + weights[1].clone() * 8.0).backward();
+        // ||0.1 * [6, 8]|| / ||[3, 4]|| = 1 / 5; the bias plays no part.
+        let ratios = mlp.update_ratios(0.1);
+        assert!((ratios[0] - 0.2).abs() < 1e-12);
+        // The second layer's weights are all zero.
+        assert_eq!(ratios[1], 0.0);
+    }
+
+    #[test]
+    fn interpolate_averages_and_checks_architecture() {
+        let a = MLP::new(2, &[3, 1]);
+        let b = MLP::new(2, &[3, 1]);
+        let mid = a.interpolate(&b, 0.5).expect("same architecture");
+        for ((m, x), y) in mid
+            .parameters_flat()
+            .iter()
+            .zip(a.parameters_flat())
+            .zip(b.parameters_flat())
+        {
+            assert!((m - (x + y) / 2.0).abs() < 1e-12);
+        }
+
+        let errors = [
+            (
+                MLP::new(2, &[3, 1, 1]),
+                ArchitectureError::LayerCount {
+                    expected: 2,
+                    got: 3,
+                },
+            ),
+            (
+                MLP::new(4, &[3, 1]),
+                ArchitectureError::InputSize {
+                    expected: 2,
+                    got: 4,
+                },
+            ),
+            (
+                MLP::new(2, &[5, 1]),
+                ArchitectureError::LayerWidth {
+                    layer: 0,
+                    expected: 3,
+                    got: 5,
+                },
+            ),
+        ];
+        for (other, expected) in errors {
+            match a.interpolate(&other, 0.5) {
+                Ok(_) => panic!("interpolate accepted a mismatched architecture"),
+                Err(err) => assert_eq!(err, expected),
+            }
+        }
+    }
+
+    #[test]
+    fn flops_counts_multiply_adds_and_relus() {
+        // (2*2*16 + 16) + (2*16*16 + 16) + 2*16*1, the output layer being linear.
+        assert_eq!(MLP::new(2, &[16, 16, 1]).flops(), 80 + 528 + 32);
+    }
+
+    #[test]
+    fn zero_width_layer_keeps_its_activation() {
+        let mlp = MLP::new(3, &[0, 2]);
+        assert_eq!(mlp.flops(), 0);
+        let clone = mlp.clone_architecture(1);
+        assert_eq!(clone.layers[0].nin(), 3);
+        assert_eq!(clone.layers[0].activation(), Activation::Relu);
+        assert_eq!(clone.layers[1].activation(), Activation::Linear);
+    }
+
+    #[test]
+    fn ensemble_with_equal_weights_averages_outputs() {
+        let a = MLP::new(2, &[3, 2]);
+        let b = MLP::new(2, &[3, 2]);
+        let x = inputs(&[0.4, -0.7]);
+        let out = ensemble(&[&a, &b], &[0.5, 0.5], &x);
+        let (pa, pb) = (a.forward(&x), b.forward(&x));
+        assert_eq!(out.len(), 2);
+        for ((o, ya), yb) in out.iter().zip(pa).zip(pb) {
+            assert!((o.data() - (ya.data() + yb.data()) / 2.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn weight_histogram_of_uniform_init_is_roughly_flat() {
+        // 1000 weights drawn uniformly from [-1, 1), so about 250 per bin.
+        let mlp = MLP::new(20, &[50]).clone_architecture(3);
+        let counts = mlp.weight_histogram(4);
+        assert_eq!(counts.iter().sum::<usize>(), 1000);
+        assert!(
+            counts.iter().all(|&c| (200..300).contains(&c)),
+            "{:?}",
+            counts
+        );
+    }
+
+    #[test]
+    fn chain_feeds_first_output_into_second() {
+        let first = MLP::new(2, &[4]);
+        let second = MLP::new(4, &[1]);
+        let x = [0.3, -0.9];
+        let expected = second.predict(&first.predict(&x));
+        let param_count = first.parameters().len() + second.parameters().len();
+        let chained = chain(first, second);
+        assert_eq!(chained.shape(), vec![2, 4, 1]);
+        assert_eq!(chained.parameters().len(), param_count);
+        assert_eq!(chained.predict(&x), expected);
+
+        let alone = chain(chained, MLP::new(1, &[]));
+        assert_eq!(alone.shape(), vec![2, 4, 1]);
+        assert_eq!(alone.predict(&x), expected);
+
+        let noisy = chain(
+            MLP::new(2, &[4]).with_dropout(0.3),
+            MLP::new(4, &[1]).with_dropout(0.3),
+        );
+        assert_eq!(noisy.dropout, 0.3);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot chain networks with dropout rates 0.5 and 0")]
+    fn chain_rejects_mismatched_dropout() {
+        chain(MLP::new(2, &[4]).with_dropout(0.5), MLP::new(4, &[1]));
+    }
+
+    #[test]
+    fn grad_flat_follows_parameters_order() {
+        let (xs, ys) = sum_dataset();
+        let mlp = MLP::new(2, &[3, 1]);
+        batch_loss(&mlp, &xs, &ys).backward();
+        let individual: Vec<f64> = mlp.parameters().iter().map(|p| p.grad()).collect();
+        assert_eq!(mlp.grad_flat(), individual);
+        assert_eq!(mlp.grad_flat().len(), mlp.parameters_flat().len());
+        assert!(individual.iter().any(|&g| g != 0.0));
+    }
+
+    #[test]
+    fn fit_timed_reports_finite_throughput() {
+        let (xs, ys) = sum_dataset();
+        let mlp = MLP::new(2, &[8, 1]);
+        let stats = mlp.fit_timed(&xs, &ys, 3, 0.05, |_, _| {});
+        assert_eq!(stats.losses.len(), 3);
+        assert_eq!(stats.epoch_durations.len(), 3);
+        for (&rate, &elapsed) in stats
+            .samples_per_sec
+            .iter()
+            .zip(stats.epoch_durations.iter())
+        {
+            assert!(rate.is_finite() && rate >= 0.0);
+            if elapsed > Duration::ZERO {
+                let samples = rate * elapsed.as_secs_f64();
+                assert!(
+                    (samples - xs.len() as f64).abs() < 1e-6,
+                    "{} samples",
+                    samples
+                );
+            } else {
+                assert_eq!(rate, 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn transfer_from_copies_the_overlapping_block() {
+        let small = MLP::new(2, &[4, 1]);
+        let large = MLP::new(2, &[8, 1]);
+        let untouched: Vec<f64> = large.layers[0].neurons[4..]
+            .iter()
+            .flat_map(|n| n.weights.iter().map(|w| w.data()))
+            .collect();
+        large.transfer_from(&small);
+        for (layer, src) in large.layers.iter().zip(small.layers.iter()) {
+            for (neuron, src_neuron) in layer.neurons.iter().zip(src.neurons.iter()) {
+                for (w, src_w) in neuron.weights.iter().zip(src_neuron.weights.iter()) {
+                    assert_eq!(w.data(), src_w.data());
+                }
+                assert_eq!(neuron.bias.data(), src_neuron.bias.data());
+            }
+        }
+        // Hidden neurons 4..8 have no counterpart and keep their weights.
+        let after: Vec<f64> = large.layers[0].neurons[4..]
+            .iter()
+            .flat_map(|n| n.weights.iter().map(|w| w.data()))
+            .collect();
+        assert_eq!(after, untouched);
+    }
+
+    #[test]
+    fn condition_number_of_diagonal_is_ratio_of_extremes() {
+        let diag = MLP::from_weights(
+            vec![vec![
+                vec![4.0, 0.0, 0.0],
+                vec![0.0, 0.5, 0.0],
+                vec![0.0, 0.0, 2.0],
+            ]],
+            vec![vec![0.0; 3]],
+            vec![Activation::Linear],
+        );
+        assert!((diag.condition_number(0, 100) - 8.0).abs() < 1e-6);
+
+        let singular = MLP::from_weights(
+            vec![vec![vec![1.0, 0.0], vec![0.0, 0.0]]],
+            vec![vec![0.0; 2]],
+            vec![Activation::Linear],
+        );
+        assert_eq!(singular.condition_number(0, 100), f64::INFINITY);
+    }
+
+    #[test]
+    fn condition_number_finds_singular_vectors_off_the_ones_direction() {
+        // M^T M = [[5, 3], [3, 5]]: singular values sqrt(8) along (1, 1) and
+        // sqrt(2) along (1, -1).
+        let mlp = MLP::from_weights(
+            vec![vec![vec![2.0, 2.0], vec![1.0, -1.0]]],
+            vec![vec![0.0; 2]],
+            vec![Activation::Linear],
+        );
+        let weights = mlp.layers[0].weight_matrix();
+        assert!((linalg::min_singular_value(&weights, 100) - 2f64.sqrt()).abs() < 1e-9);
+        assert!((mlp.condition_number(0, 100) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weight_sensitivity_of_linear_net_is_the_input() {
+        let mlp = MLP::from_weights(
+            vec![vec![vec![0.3, -2.0, 5.0]]],
+            vec![vec![1.0]],
+            vec![Activation::Linear],
+        );
+        let x = [1.5, -0.25, 4.0];
+        assert_eq!(mlp.weight_sensitivity(&x), x.to_vec());
+        assert!(mlp.grad_flat().iter().all(|&g| g == 0.0));
+    }
+
+    #[test]
+    fn fit_with_loss_mse_matches_fit_and_mae_differs() {
+        let (xs, ys) = sum_dataset();
+        // Two outputs: only the first is trained, as in fit.
+        let base = MLP::new(2, &[4, 2]);
+        let (a, b, c) = (base.deep_clone(), base.deep_clone(), base.deep_clone());
+        let fit_losses = a.fit(&xs, &ys, 5, 0.05, |_, _| {});
+        let mse_losses = b.fit_with_loss(&xs, &ys, 5, 0.05, loss::mse);
+        for (f, m) in fit_losses.iter().zip(mse_losses.iter()) {
+            assert!((f - m).abs() < 1e-12);
+        }
+        assert_eq!(a.parameters_flat(), b.parameters_flat());
+
+        let mae_losses = c.fit_with_loss(&xs, &ys, 5, 0.05, loss::mae);
+        // Before the first update the losses are the mean absolute and
+        // mean squared errors of the same predictions.
+        let errors: Vec<f64> = xs
+            .iter()
+            .zip(ys.iter())
+            .map(|(x, y)| base.forward(x)[0].data() - y.data())
+            .collect();
+        let n = errors.len() as f64;
+        assert!((mae_losses[0] - errors.iter().map(|e| e.abs()).sum::<f64>() / n).abs() < 1e-12);
+        assert!((mse_losses[0] - errors.iter().map(|e| e * e).sum::<f64>() / n).abs() < 1e-12);
+        assert_ne!(c.parameters_flat(), b.parameters_flat());
+    }
+
+    #[test]
+    fn predict_fixed_tracks_float_within_grid_error() {
+        let mlp = MLP::new(3, &[8, 8, 2]);
+        let x = [0.25, -0.6, 0.9];
+        for frac_bits in [8, 16] {
+            let one = (1i64 << frac_bits) as f64;
+            let fixed_x: Vec<i32> = x.iter().map(|&v| (v * one).round() as i32).collect();
+            let fixed = mlp.predict_fixed(&fixed_x, frac_bits);
+            // Each rounding costs at most half a step; allow a few dozen
+            // steps for the errors accumulated through three layers.
+            let tolerance = 50.0 / one;
+            for (f, p) in fixed.iter().zip(mlp.predict(&x)) {
+                assert!(
+                    (*f as f64 / one - p).abs() < tolerance,
+                    "{} vs {} at {} bits",
+                    *f as f64 / one,
+                    p,
+                    frac_bits
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "frac_bits must be below 31")]
+    fn predict_fixed_rejects_too_many_fraction_bits() {
+        MLP::new(1, &[1]).predict_fixed(&[0], 31);
+    }
+
+    #[test]
+    fn predict_fixed_saturates_instead_of_overflowing() {
+        let mlp = MLP::from_weights(
+            vec![vec![vec![1.0; 8]]],
+            vec![vec![0.0]],
+            vec![Activation::Linear],
+        );
+        assert_eq!(mlp.predict_fixed(&[i32::MAX; 8], 30), vec![i32::MAX]);
+        assert_eq!(mlp.predict_fixed(&[i32::MIN; 8], 30), vec![i32::MIN]);
+    }
+
+    #[test]
+    fn lipschitz_bound_is_product_of_spectral_norms() {
+        let mlp = MLP::from_weights(
+            vec![vec![vec![2.0, 0.0], vec![0.0, 0.5]], vec![vec![3.0, 4.0]]],
+            vec![vec![0.0, 0.0], vec![0.0]],
+            vec![Activation::Relu, Activation::Linear],
+        );
+        // ||diag(2, 0.5)|| = 2 and ||[3, 4]|| = 5.
+        assert!((mlp.lipschitz_bound(100) - 10.0).abs() < 1e-6);
+        let random = MLP::new(3, &[5, 4, 1]);
+        let product: f64 = (0..3).map(|i| random.spectral_norm(i, 100)).product();
+        assert!((random.lipschitz_bound(100) - product).abs() < 1e-9);
+    }
+
+    #[test]
+    fn integrated_gradients_of_linear_net() {
+        let w = [0.5, -2.0, 3.0];
+        let mlp = MLP::from_weights(
+            vec![vec![w.to_vec()]],
+            vec![vec![0.7]],
+            vec![Activation::Linear],
+        );
+        let x = [1.0, 2.0, -1.0];
+        let baseline = [0.0, 1.0, 1.0];
+        let attributions = mlp.integrated_gradients(&x, &baseline, 7);
+        for ((a, wi), (xi, bi)) in attributions.iter().zip(w).zip(x.iter().zip(baseline)) {
+            assert!((a - (xi - bi) * wi).abs() < 1e-12);
+        }
+        assert!(mlp.grad_flat().iter().all(|&g| g == 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one step")]
+    fn integrated_gradients_rejects_zero_steps() {
+        MLP::new(1, &[1]).integrated_gradients(&[1.0], &[0.0], 0);
+    }
+
+    #[test]
+    fn effective_dof_of_linear_model_is_parameter_count() {
+        let linear = MLP::from_weights(
+            vec![vec![vec![0.5, -1.0, 2.0]]],
+            vec![vec![0.1]],
+            vec![Activation::Linear],
+        );
+        assert_eq!(linear.effective_dof(&[0.0, 0.0, 0.0]), 4.0);
+        assert_eq!(linear.effective_dof(&[1.0, -2.0, 0.5]), 4.0);
+        assert!(linear.grad_flat().iter().all(|&g| g == 0.0));
+
+        // Every output counts: two outputs of three inputs have 8 parameters.
+        let wide = MLP::from_weights(
+            vec![vec![vec![0.5, -1.0, 2.0], vec![1.0, 0.3, -0.7]]],
+            vec![vec![0.1, -0.2]],
+            vec![Activation::Linear],
+        );
+        assert_eq!(wide.effective_dof(&[1.0, -2.0, 0.5]), 8.0);
+
+        // A deeper linear net is still an affine map of 2 inputs.
+        let deep = MLP::from_weights(
+            vec![
+                vec![vec![1.0, 2.0], vec![-1.0, 0.5], vec![0.3, 0.3]],
+                vec![vec![1.0, -2.0, 0.5]],
+            ],
+            vec![vec![0.0; 3], vec![0.0]],
+            vec![Activation::Linear, Activation::Linear],
+        );
+        assert_eq!(deep.effective_dof(&[0.2, 0.4]), 3.0);
+
+        // With every hidden ReLU off only the output bias matters.
+        let dead = MLP::from_weights(
+            vec![vec![vec![1.0, 1.0], vec![1.0, -1.0]], vec![vec![1.0, 1.0]]],
+            vec![vec![-100.0, -100.0], vec![0.0]],
+            vec![Activation::Relu, Activation::Linear],
+        );
+        assert_eq!(dead.effective_dof(&[0.5, 0.5]), 1.0);
+    }
+
+    #[test]
+    fn predict_mc_dropout_variance_follows_dropout_rate() {
+        let mlp = MLP::with_bias_init(2, &[16, 1], 0.1);
+        let x = [0.5, -0.3];
+        let (mean, variance) = mlp.predict_mc_dropout(&x, 20, 1);
+        assert_eq!(variance, 0.0);
+        assert!((mean - mlp.predict(&x)[0]).abs() < 1e-12);
+
+        let noisy = mlp.deep_clone().with_dropout(0.5);
+        let (_, variance) = noisy.predict_mc_dropout(&x, 50, 1);
+        assert!(variance > 0.0);
+        assert_eq!(noisy.predict_mc_dropout(&x, 50, 1).1, variance);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one pass")]
+    fn predict_mc_dropout_rejects_zero_passes() {
+        MLP::new(1, &[2, 1]).predict_mc_dropout(&[0.0], 0, 0);
+    }
+
+    #[test]
+    fn predict_mc_dropout_on_empty_network_is_identity() {
+        let mlp = MLP::new(2, &[]).with_dropout(0.5);
+        assert_eq!(mlp.predict_mc_dropout(&[0.5, -1.0], 10, 3), (0.5, 0.0));
+    }
+
+    #[test]
+    fn sparsity_reports_pruned_fraction() {
+        let mlp = MLP::from_weights(
+            vec![vec![vec![0.05, -2.0], vec![0.01, 3.0]]],
+            vec![vec![0.0, 0.0]],
+            vec![Activation::Linear],
+        );
+        assert_eq!(mlp.sparsity(), 0.0);
+        assert_eq!(mlp.prune(0.1), 2);
+        // The zero biases don't count.
+        assert_eq!(mlp.sparsity(), 0.5);
+        assert_eq!(MLP::new(3, &[0]).sparsity(), 0.0);
+    }
+
+    #[test]
+    fn update_custom_reproduces_sgd() {
+        let (xs, ys) = sum_dataset();
+        let custom = MLP::new(2, &[4, 1]);
+        let sgd = custom.deep_clone();
+        let mut optimizer = optim::SGD::new(sgd.parameters(), 0.1);
+        for _ in 0..3 {
+            custom.zero_grad();
+            batch_loss(&custom, &xs, &ys).backward();
+            custom.update_custom(|d, g| d - 0.1 * g);
+
+            optimizer.zero_grad();
+            batch_loss(&sgd, &xs, &ys).backward();
+            optimizer.step();
+        }
+        assert_eq!(custom.parameters_flat(), sgd.parameters_flat());
+    }
+}
@@ -0,0 +1,143 @@
+// Small dense helpers on row-major weight matrices (rows = neurons).
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+pub(crate) fn mat_vec(m: &[Vec<f64>], v: &[f64]) -> Vec<f64> {
+    m.iter()
+        .map(|row| row.iter().zip(v.iter()).map(|(a, b)| a * b).sum())
+        .collect()
+}
+
+pub(crate) fn mat_t_vec(m: &[Vec<f64>], v: &[f64]) -> Vec<f64> {
+    let ncols = m.first().map_or(0, |row| row.len());
+    let mut out = vec![0.0; ncols];
+    for (row, &vi) in m.iter().zip(v.iter()) {
+        for (o, &mij) in out.iter_mut().zip(row.iter()) {
+            *o += mij * vi;
+        }
+    }
+    out
+}
+
+pub(crate) fn norm(v: &[f64]) -> f64 {
+    v.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+// Seeded random unit vector to start power iteration from. A fixed vector
+// such as all ones is orthogonal to the singular vectors of many simple
+// matrices (e.g. [[1, -1]]), which the iteration then never finds.
+fn start_vector(n: usize) -> Vec<f64> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let v: Vec<f64> = (0..n).map(|_| rng.random_range(-1.0..1.0)).collect();
+    let n = norm(&v);
+    v.iter().map(|x| x / n).collect()
+}
+
+// Largest singular value via power iteration on M^T M.
+pub(crate) fn spectral_norm(m: &[Vec<f64>], iterations: usize) -> f64 {
+    let ncols = m.first().map_or(0, |row| row.len());
+    if ncols == 0 {
+        return 0.0;
+    }
+    let mut v = start_vector(ncols);
+    for _ in 0..iterations {
+        let next = mat_t_vec(m, &mat_vec(m, &v));
+        let n = norm(&next);
+        if n == 0.0 {
+            return 0.0;
+        }
+        v = next.iter().map(|x| x / n).collect();
+    }
+    norm(&mat_vec(m, &v))
+}
+
+// Solves a x = b by Gaussian elimination with partial pivoting; None if a is
+// singular to working precision.
+pub(crate) fn solve(a: &[Vec<f64>], b: &[f64]) -> Option<Vec<f64>> {
+    let n = b.len();
+    let mut m: Vec<Vec<f64>> = a
+        .iter()
+        .zip(b.iter())
+        .map(|(row, &bi)| row.iter().copied().chain([bi]).collect())
+        .collect();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| m[i][col].abs().total_cmp(&m[j][col].abs()))?;
+        if m[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        m.swap(col, pivot);
+        for row in col + 1..n {
+            let factor = m[row][col] / m[col][col];
+            let (upper, lower) = m.split_at_mut(row);
+            for (x, &p) in lower[0][col..].iter_mut().zip(upper[col][col..].iter()) {
+                *x -= factor * p;
+            }
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let tail: f64 = (row + 1..n).map(|k| m[row][k] * x[k]).sum();
+        x[row] = (m[row][n] - tail) / m[row][row];
+    }
+    Some(x)
+}
+
+// Smallest singular value via inverse power iteration on M^T M; 0 when
+// M^T M is singular, e.g. a layer with fewer neurons than inputs.
+pub(crate) fn min_singular_value(m: &[Vec<f64>], iterations: usize) -> f64 {
+    let ncols = m.first().map_or(0, |row| row.len());
+    if ncols == 0 {
+        return 0.0;
+    }
+    let gram: Vec<Vec<f64>> = (0..ncols)
+        .map(|i| {
+            let mut e = vec![0.0; ncols];
+            e[i] = 1.0;
+            mat_t_vec(m, &mat_vec(m, &e))
+        })
+        .collect();
+    let mut v = start_vector(ncols);
+    for _ in 0..iterations {
+        let next = match solve(&gram, &v) {
+            Some(next) => next,
+            None => return 0.0,
+        };
+        let n = norm(&next);
+        v = next.iter().map(|x| x / n).collect();
+    }
+    norm(&mat_vec(m, &v))
+}
+
+// Row rank by Gaussian elimination with partial pivoting; entries below
+// 1e-9 times the largest magnitude count as zero.
+pub(crate) fn rank(m: &[Vec<f64>]) -> usize {
+    let mut m = m.to_vec();
+    let ncols = m.first().map_or(0, |row| row.len());
+    let scale = m.iter().flatten().fold(0.0f64, |acc, x| acc.max(x.abs()));
+    if scale == 0.0 {
+        return 0;
+    }
+    let tol = 1e-9 * scale;
+    let mut rank = 0;
+    for col in 0..ncols {
+        if rank == m.len() {
+            break;
+        }
+        let pivot = (rank..m.len())
+            .max_by(|&i, &j| m[i][col].abs().total_cmp(&m[j][col].abs()))
+            .unwrap();
+        if m[pivot][col].abs() < tol {
+            continue;
+        }
+        m.swap(rank, pivot);
+        for row in rank + 1..m.len() {
+            let factor = m[row][col] / m[rank][col];
+            let (upper, lower) = m.split_at_mut(row);
+            for (x, &p) in lower[0][col..].iter_mut().zip(upper[rank][col..].iter()) {
+                *x -= factor * p;
+            }
+        }
+        rank += 1;
+    }
+    rank
+}
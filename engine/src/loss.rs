@@ -0,0 +1,424 @@
+use crate::MLP;
+use crate::ops::{log_softmax, softmax};
+use crate::util::kahan_sum;
+use crate::value::Value;
+
+// Averages the squared error over every sample and every output dimension.
+pub fn mse_multi(preds: &[Vec<Value>], targets: &[Vec<f64>]) -> Value {
+    let mut total = Value::new(0.0);
+    let mut count = 0;
+    for (pred, target) in preds.iter().zip(targets.iter()) {
+        for (p, &t) in pred.iter().zip(target.iter()) {
+            let diff = p.clone() - Value::new(t);
+            total = total + diff.clone() * diff;
+            count += 1;
+        }
+    }
+    assert!(count > 0, "expected at least 1 prediction, got 0");
+    total * (1.0 / count as f64)
+}
+
+// MSE of the first output over the whole batch as a single Value, so one
+// backward call covers every sample. Gradients match calling backward on
+// each per-sample squared error, scaled by 1 / xs.len().
+pub fn batch_loss(mlp: &MLP, xs: &[Vec<Value>], ys: &[Value]) -> Value {
+    let mut total = Value::new(0.0);
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        let pred = mlp.forward(x)[0].clone();
+        let diff = pred - y.clone();
+        total = total + diff.clone() * diff;
+    }
+    total * (1.0 / xs.len() as f64)
+}
+
+// Value of batch_loss computed through MLP::predict, for validation sets
+// where no gradients are needed and no graph should be kept alive.
+pub fn eval_loss(mlp: &MLP, xs: &[Vec<f64>], ys: &[f64]) -> f64 {
+    let squared: Vec<f64> = xs
+        .iter()
+        .zip(ys.iter())
+        .map(|(x, &y)| {
+            let diff = mlp.predict(x)[0] - y;
+            diff * diff
+        })
+        .collect();
+    kahan_sum(&squared) / xs.len() as f64
+}
+
+// Squared error of the first output for each sample; the mean of the result
+// is the value of batch_loss.
+pub fn per_sample_loss(mlp: &MLP, xs: &[Vec<Value>], ys: &[Value]) -> Vec<f64> {
+    xs.iter()
+        .zip(ys.iter())
+        .map(|(x, y)| {
+            let diff = mlp.forward(x)[0].data() - y.data();
+            diff * diff
+        })
+        .collect()
+}
+
+pub fn mse(preds: &[Value], targets: &[Value]) -> Value {
+    let mut total = Value::new(0.0);
+    for (pred, target) in preds.iter().zip(targets.iter()) {
+        let diff = pred.clone() - target.clone();
+        total = total + diff.clone() * diff;
+    }
+    total * (1.0 / preds.len() as f64)
+}
+
+// |d| is built as relu(d) + relu(-d), so the gradient is sign(d) and 0 at d = 0.
+pub fn mae(preds: &[Value], targets: &[Value]) -> Value {
+    let mut total = Value::new(0.0);
+    for (pred, target) in preds.iter().zip(targets.iter()) {
+        let diff = pred.clone() - target.clone();
+        total = total + diff.clone().relu() + (diff * -1.0).relu();
+    }
+    total * (1.0 / preds.len() as f64)
+}
+
+// Each sample's squared error is scaled by its weight before averaging over
+// the number of samples.
+pub fn weighted_mse(preds: &[Value], targets: &[Value], weights: &[f64]) -> Value {
+    assert_eq!(preds.len(), weights.len(), "expected one weight per sample");
+    let mut total = Value::new(0.0);
+    for ((pred, target), &w) in preds.iter().zip(targets.iter()).zip(weights.iter()) {
+        let diff = pred.clone() - target.clone();
+        total = total + diff.clone() * diff * w;
+    }
+    total * (1.0 / preds.len() as f64)
+}
+
+// Probabilities outside [PROB_EPS, 1 - PROB_EPS] are clamped so ln never sees 0.
+const PROB_EPS: f64 = 1e-7;
+
+// The clamp is straight-through: the value is shifted by a constant offset,
+// so the gradient still reaches p and a confidently wrong prediction gets
+// pushed back.
+fn clamp_prob(p: &Value) -> Value {
+    let data = p.data();
+    let clamped = data.clamp(PROB_EPS, 1.0 - PROB_EPS);
+    if clamped == data {
+        p.clone()
+    } else {
+        p.clone() + (clamped - data)
+    }
+}
+
+// Binary focal loss -(1 - p_t)^gamma * ln(p_t), where pred is the predicted
+// probability of the positive class and p_t is the probability assigned to
+// the true class (pred if target is 1.0, 1 - pred otherwise).
+pub fn focal_loss(pred: &Value, target: f64, gamma: f64) -> Value {
+    let p = clamp_prob(pred);
+    let pt = if target >= 0.5 {
+        p
+    } else {
+        Value::new(1.0) - p
+    };
+    let weight = (Value::new(1.0) - pt.clone()).pow(gamma);
+    weight * pt.ln() * -1.0
+}
+
+// KL(P || Q) where P = softmax(p_logits) is the teacher and Q = softmax(q_logits)
+// the student.
+pub fn kl_div(p_logits: &[Value], q_logits: &[Value]) -> Value {
+    assert_eq!(
+        p_logits.len(),
+        q_logits.len(),
+        "kl_div: logit vectors must have equal length"
+    );
+    let p = softmax(p_logits);
+    let log_p = log_softmax(p_logits);
+    let log_q = log_softmax(q_logits);
+    let mut total = Value::new(0.0);
+    for ((pi, lpi), lqi) in p.into_iter().zip(log_p).zip(log_q) {
+        total = total + pi * (lpi - lqi);
+    }
+    total
+}
+
+fn squared_distance(a: &[Value], b: &[Value]) -> Value {
+    assert_eq!(
+        a.len(),
+        b.len(),
+        "embeddings must have equal length, got {} and {}",
+        a.len(),
+        b.len()
+    );
+    let mut total = Value::new(0.0);
+    for (x, y) in a.iter().zip(b.iter()) {
+        let diff = x.clone() - y.clone();
+        total = total + diff.clone() * diff;
+    }
+    total
+}
+
+// A tiny offset keeps the sqrt gradient finite when the embeddings coincide.
+fn distance(a: &[Value], b: &[Value]) -> Value {
+    (squared_distance(a, b) + 1e-12).pow(0.5)
+}
+
+// label = 1.0 marks a similar pair, pulled together by the squared distance;
+// label = 0.0 marks a dissimilar pair, pushed apart until their distance
+// reaches margin: label * d^2 + (1 - label) * max(0, margin - d)^2.
+pub fn contrastive_loss(a: &[Value], b: &[Value], label: f64, margin: f64) -> Value {
+    let sq_dist = squared_distance(a, b);
+    let gap = (Value::new(margin) - distance(a, b)).relu();
+    sq_dist * label + gap.clone() * gap * (1.0 - label)
+}
+
+// max(0, d(anchor, positive) - d(anchor, negative) + margin) with Euclidean distances.
+pub fn triplet_loss(
+    anchor: &[Value],
+    positive: &[Value],
+    negative: &[Value],
+    margin: f64,
+) -> Value {
+    (distance(anchor, positive) - distance(anchor, negative) + margin).relu()
+}
+
+// Cross-entropy against a smoothed target distribution: the target class
+// gets 1 - smoothing and the remaining mass is split evenly over the other
+// classes. smoothing = 0.0 gives plain cross-entropy.
+pub fn cross_entropy_smooth(logits: &[Value], target_index: usize, smoothing: f64) -> Value {
+    assert!(
+        target_index < logits.len(),
+        "target index {} out of range for {} logits",
+        target_index,
+        logits.len()
+    );
+    let others = if logits.len() > 1 {
+        smoothing / (logits.len() - 1) as f64
+    } else {
+        0.0
+    };
+    let mut total = Value::new(0.0);
+    for (i, log_p) in log_softmax(logits).into_iter().enumerate() {
+        let q = if i == target_index {
+            1.0 - smoothing
+        } else {
+            others
+        };
+        total = total + log_p * q;
+    }
+    total * -1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // y = x1 + x2 on a few points.
+    fn sum_dataset() -> (Vec<Vec<Value>>, Vec<Value>) {
+        let points = [[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0], [0.5, 0.5]];
+        let xs = points.iter().map(|p| values(p)).collect();
+        let ys = points.iter().map(|p| Value::new(p[0] + p[1])).collect();
+        (xs, ys)
+    }
+
+    fn values(xs: &[f64]) -> Vec<Value> {
+        xs.iter().map(|&x| Value::new(x)).collect()
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn mse_multi_averages_over_outputs() {
+        let preds = vec![values(&[1.0, 2.0]), values(&[0.0, -1.0])];
+        let targets = vec![vec![0.0, 2.0], vec![1.0, 1.0]];
+        let loss = mse_multi(&preds, &targets);
+        // (1 + 0 + 1 + 4) / 4
+        assert_close(loss.data(), 1.5);
+        loss.backward();
+        // d/dp of (p - t)^2 / 4 is (p - t) / 2.
+        assert_close(preds[0][0].grad(), 0.5);
+        assert_close(preds[1][1].grad(), -1.0);
+    }
+
+    #[test]
+    fn mse_multi_backpropagates_through_two_output_mlp() {
+        let mlp = MLP::new(2, &[3, 2]);
+        let (xs, _) = sum_dataset();
+        let preds: Vec<Vec<Value>> = xs.iter().map(|x| mlp.forward(x)).collect();
+        let targets: Vec<Vec<f64>> = (0..xs.len())
+            .map(|i| vec![i as f64 + 5.0, -(i as f64) - 5.0])
+            .collect();
+        mse_multi(&preds, &targets).backward();
+        let n = (preds.len() * 2) as f64;
+        for (pred, target) in preds.iter().zip(targets.iter()) {
+            for (p, &t) in pred.iter().zip(target.iter()) {
+                assert_ne!(p.grad(), 0.0);
+                assert_close(p.grad(), 2.0 * (p.data() - t) / n);
+            }
+        }
+        assert!(mlp.parameters().iter().any(|p| p.grad() != 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected at least 1 prediction, got 0")]
+    fn mse_multi_rejects_empty_batch() {
+        mse_multi(&[], &[]);
+    }
+
+    #[test]
+    fn focal_loss_scales_bce_by_modulating_factor() {
+        let pred = Value::new(0.8);
+        let bce = -(0.8f64).ln();
+        // (1 - p_t)^gamma with p_t = 0.8 for a positive target.
+        assert_close(focal_loss(&pred, 1.0, 2.0).data(), 0.2f64.powi(2) * bce);
+        assert_close(focal_loss(&pred, 1.0, 0.0).data(), bce);
+        // For a negative target p_t = 0.2.
+        assert_close(
+            focal_loss(&pred, 0.0, 2.0).data(),
+            0.8f64.powi(2) * -(0.2f64).ln(),
+        );
+    }
+
+    #[test]
+    fn focal_loss_keeps_gradient_when_clamped() {
+        // p = 0 for a positive target is clamped to PROB_EPS, but the
+        // gradient still flows to pred and pushes it up.
+        let pred = Value::new(0.0);
+        focal_loss(&pred, 1.0, 2.0).backward();
+        let pt = PROB_EPS;
+        let expected = 2.0 * (1.0 - pt) * pt.ln() - (1.0 - pt).powi(2) / pt;
+        assert!(pred.grad() < 0.0);
+        assert!((pred.grad() - expected).abs() < 1e-9 * expected.abs());
+
+        let pred = Value::new(1.0);
+        focal_loss(&pred, 0.0, 2.0).backward();
+        assert!(pred.grad() > 0.0);
+    }
+
+    #[test]
+    fn kl_div_zero_when_equal_and_trains_student() {
+        let teacher = values(&[1.0, 2.0, 0.5]);
+        assert!(kl_div(&teacher, &values(&[1.0, 2.0, 0.5])).data().abs() < 1e-12);
+
+        let student = values(&[0.0, 0.0, 0.0]);
+        let loss = kl_div(&teacher, &student);
+        assert!(loss.data() > 0.0);
+        loss.backward();
+        // d KL / d q_logits = softmax(q) - softmax(p): the student is pushed
+        // toward the teacher's most likely class.
+        let p = softmax(&teacher);
+        for (qi, pi) in student.iter().zip(p.iter()) {
+            assert_close(qi.grad(), 1.0 / 3.0 - pi.data());
+        }
+    }
+
+    #[test]
+    fn batch_loss_matches_per_sample_backward() {
+        let (xs, ys) = sum_dataset();
+        let mlp = MLP::new(2, &[4, 1]);
+        let grads = |m: &MLP| m.parameters().iter().map(|p| p.grad()).collect::<Vec<_>>();
+        batch_loss(&mlp, &xs, &ys).backward();
+        let batch_grads = grads(&mlp);
+
+        mlp.zero_grad();
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            let diff = mlp.forward(x)[0].clone() - y.clone();
+            (diff.clone() * diff).backward();
+        }
+        for (b, s) in batch_grads.iter().zip(grads(&mlp)) {
+            assert_close(*b, s / xs.len() as f64);
+        }
+    }
+
+    #[test]
+    fn contrastive_loss_pulls_similar_pair_together() {
+        let a = values(&[1.0, 0.0]);
+        let b = values(&[0.0, 1.0]);
+        let loss = contrastive_loss(&a, &b, 1.0, 1.0);
+        assert_close(loss.data(), 2.0);
+        loss.backward();
+        // Gradient descent on a moves it toward b.
+        assert_close(a[0].grad(), 2.0);
+        assert_close(a[1].grad(), -2.0);
+
+        // A dissimilar pair beyond the margin costs nothing.
+        assert_close(contrastive_loss(&a, &b, 0.0, 1.0).data(), 0.0);
+    }
+
+    #[test]
+    fn triplet_loss_penalizes_violations() {
+        let anchor = values(&[0.0, 0.0]);
+        let positive = values(&[3.0, 0.0]);
+        let negative = values(&[1.0, 0.0]);
+        // d(a, p) - d(a, n) + margin = 3 - 1 + 0.5
+        let loss = triplet_loss(&anchor, &positive, &negative, 0.5);
+        assert!((loss.data() - 2.5).abs() < 1e-6);
+        loss.backward();
+        assert!(positive[0].grad() > 0.0);
+        assert!(negative[0].grad() < 0.0);
+
+        assert_eq!(triplet_loss(&anchor, &negative, &positive, 0.5).data(), 0.0);
+    }
+
+    #[test]
+    fn per_sample_loss_mean_is_batch_loss() {
+        let (xs, ys) = sum_dataset();
+        let mlp = MLP::new(2, &[4, 1]);
+        let losses = per_sample_loss(&mlp, &xs, &ys);
+        assert_eq!(losses.len(), xs.len());
+        let mean = losses.iter().sum::<f64>() / losses.len() as f64;
+        assert_close(mean, batch_loss(&mlp, &xs, &ys).data());
+    }
+
+    #[test]
+    fn weighted_mse_scales_each_sample() {
+        let preds = values(&[1.0, 3.0]);
+        let targets = values(&[0.0, 1.0]);
+        // Unit weights give the plain mean squared error: (1 + 4) / 2.
+        assert_close(weighted_mse(&preds, &targets, &[1.0, 1.0]).data(), 2.5);
+        // Doubling the second sample's weight doubles its share: (1 + 2 * 4) / 2.
+        let loss = weighted_mse(&preds, &targets, &[1.0, 2.0]);
+        assert_close(loss.data(), 4.5);
+        loss.backward();
+        assert_close(preds[0].grad(), 1.0);
+        assert_close(preds[1].grad(), 4.0);
+    }
+
+    #[test]
+    fn cross_entropy_smooth_softens_target() {
+        let logits = values(&[2.0, 0.5, -1.0]);
+        let plain = -log_softmax(&logits)[0].data();
+        assert_close(cross_entropy_smooth(&logits, 0, 0.0).data(), plain);
+
+        // The gradient on the target logit is softmax - q, so smoothing
+        // lowers q from 1 and shrinks the push toward the target.
+        let hard = values(&[2.0, 0.5, -1.0]);
+        cross_entropy_smooth(&hard, 0, 0.0).backward();
+        let soft = values(&[2.0, 0.5, -1.0]);
+        cross_entropy_smooth(&soft, 0, 0.2).backward();
+        let p0 = softmax(&logits)[0].data();
+        assert_close(hard[0].grad(), p0 - 1.0);
+        assert_close(soft[0].grad(), p0 - 0.8);
+        assert!(soft[0].grad().abs() < hard[0].grad().abs());
+    }
+
+    #[test]
+    #[should_panic(expected = "target index 3 out of range for 3 logits")]
+    fn cross_entropy_smooth_rejects_out_of_range_target() {
+        cross_entropy_smooth(&values(&[2.0, 0.5, -1.0]), 3, 0.1);
+    }
+
+    #[test]
+    fn eval_loss_matches_batch_loss_without_a_graph() {
+        let (xs, ys) = sum_dataset();
+        let mlp = MLP::new(2, &[4, 1]);
+        let raw_xs: Vec<Vec<f64>> = xs
+            .iter()
+            .map(|x| x.iter().map(|v| v.data()).collect())
+            .collect();
+        let raw_ys: Vec<f64> = ys.iter().map(|y| y.data()).collect();
+        let loss = batch_loss(&mlp, &xs, &ys);
+        loss.backward();
+        let grads = mlp.grad_flat();
+        assert!(grads.iter().any(|&g| g != 0.0));
+        assert_close(eval_loss(&mlp, &raw_xs, &raw_ys), loss.data());
+        // No graph is built, so existing grads are left exactly as they were.
+        assert_eq!(mlp.grad_flat(), grads);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::value::Value;
+
+// Samples with a zero target are skipped, since their relative error is undefined.
+// Returns NaN if every target is zero.
+pub fn mape(preds: &[Value], targets: &[Value]) -> f64 {
+    let mut total = 0.0;
+    let mut count = 0;
+    for (pred, target) in preds.iter().zip(targets.iter()) {
+        let t = target.data();
+        if t == 0.0 {
+            continue;
+        }
+        total += ((pred.data() - t) / t).abs();
+        count += 1;
+    }
+    total / count as f64
+}
+
+// A prediction counts as correct when fewer than k logits are strictly
+// greater than the target's logit, so ties resolve in the target's favor.
+pub fn top_k_accuracy(preds: &[Vec<Value>], targets: &[usize], k: usize) -> f64 {
+    let mut correct = 0;
+    for (logits, &target) in preds.iter().zip(targets.iter()) {
+        let target_logit = logits[target].data();
+        let higher = logits.iter().filter(|l| l.data() > target_logit).count();
+        if higher < k {
+            correct += 1;
+        }
+    }
+    correct as f64 / preds.len() as f64
+}
+
+fn argmax(logits: &[Value]) -> usize {
+    let mut best = 0;
+    for (i, l) in logits.iter().enumerate() {
+        if l.data() > logits[best].data() {
+            best = i;
+        }
+    }
+    best
+}
+
+// Per-class (precision, recall) from the confusion matrix of argmax
+// predictions. A class that is never predicted (or never present) gets a
+// precision (or recall) of 0.0.
+pub fn precision_recall(
+    preds: &[Vec<Value>],
+    targets: &[usize],
+    num_classes: usize,
+) -> Vec<(f64, f64)> {
+    // confusion[actual][predicted]
+    let mut confusion = vec![vec![0usize; num_classes]; num_classes];
+    for (logits, &target) in preds.iter().zip(targets.iter()) {
+        confusion[target][argmax(logits)] += 1;
+    }
+
+    (0..num_classes)
+        .map(|c| {
+            let tp = confusion[c][c] as f64;
+            let predicted: usize = (0..num_classes).map(|a| confusion[a][c]).sum();
+            let actual: usize = confusion[c].iter().sum();
+            let precision = if predicted > 0 {
+                tp / predicted as f64
+            } else {
+                0.0
+            };
+            let recall = if actual > 0 { tp / actual as f64 } else { 0.0 };
+            (precision, recall)
+        })
+        .collect()
+}
+
+// Area under the ROC curve from the Mann-Whitney U statistic: the chance a
+// random positive (label != 0) scores above a random negative, with ties
+// counting half. Returns NaN unless both classes are present.
+pub fn roc_auc(scores: &[f64], labels: &[u8]) -> f64 {
+    assert_eq!(
+        scores.len(),
+        labels.len(),
+        "roc_auc: scores and labels must have equal length, got {} and {}",
+        scores.len(),
+        labels.len()
+    );
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| scores[a].total_cmp(&scores[b]));
+
+    // 1-based ranks, averaged over runs of tied scores.
+    let mut ranks = vec![0.0; scores.len()];
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i;
+        while j + 1 < order.len() && scores[order[j + 1]] == scores[order[i]] {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        for &k in &order[i..=j] {
+            ranks[k] = rank;
+        }
+        i = j + 1;
+    }
+
+    let positives = labels.iter().filter(|&&l| l != 0).count() as f64;
+    let negatives = labels.len() as f64 - positives;
+    let rank_sum: f64 = ranks
+        .iter()
+        .zip(labels.iter())
+        .filter(|&(_, &l)| l != 0)
+        .map(|(r, _)| r)
+        .sum();
+    (rank_sum - positives * (positives + 1.0) / 2.0) / (positives * negatives)
+}
+
+// (threshold, true positives, false positives) when predicting positive for
+// score >= threshold, for each distinct score from highest to lowest.
+fn threshold_counts(scores: &[f64], labels: &[u8]) -> Vec<(f64, usize, usize)> {
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+
+    let mut counts = Vec::new();
+    let (mut tp, mut fp) = (0, 0);
+    for (i, &k) in order.iter().enumerate() {
+        if labels[k] != 0 {
+            tp += 1;
+        } else {
+            fp += 1;
+        }
+        let last_of_tie = order
+            .get(i + 1)
+            .is_none_or(|&next| scores[next] != scores[k]);
+        if last_of_tie {
+            counts.push((scores[k], tp, fp));
+        }
+    }
+    counts
+}
+
+// Average precision: precision at each distinct threshold weighted by the
+// increase in recall it brings. Returns NaN if there are no positives.
+pub fn pr_auc(scores: &[f64], labels: &[u8]) -> f64 {
+    let positives = labels.iter().filter(|&&l| l != 0).count() as f64;
+    if positives == 0.0 {
+        return f64::NAN;
+    }
+    let mut ap = 0.0;
+    let mut prev_recall = 0.0;
+    for (_, tp, fp) in threshold_counts(scores, labels) {
+        let recall = tp as f64 / positives;
+        let precision = tp as f64 / (tp + fp) as f64;
+        ap += (recall - prev_recall) * precision;
+        prev_recall = recall;
+    }
+    ap
+}
+
+// Sweeps every distinct score as a threshold (predicting positive for
+// score >= threshold) and returns the threshold with the highest F1 along
+// with that F1. The higher threshold wins ties.
+pub fn best_threshold_f1(scores: &[f64], labels: &[u8]) -> (f64, f64) {
+    let positives = labels.iter().filter(|&&l| l != 0).count();
+    let mut best = (f64::NAN, 0.0);
+    for (threshold, tp, fp) in threshold_counts(scores, labels) {
+        let false_negatives = positives - tp;
+        let f1 = 2.0 * tp as f64 / (2 * tp + fp + false_negatives) as f64;
+        if best.0.is_nan() || f1 > best.1 {
+            best = (threshold, f1);
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(xs: &[f64]) -> Vec<Value> {
+        xs.iter().map(|&x| Value::new(x)).collect()
+    }
+
+    #[test]
+    fn mape_skips_zero_targets() {
+        // |110 - 100| / 100 = 0.1 and |45 - 50| / 50 = 0.1; the zero target is ignored.
+        let preds = values(&[110.0, 45.0, 3.0]);
+        let targets = values(&[100.0, 50.0, 0.0]);
+        assert!((mape(&preds, &targets) - 0.1).abs() < 1e-12);
+        assert!(mape(&values(&[1.0]), &values(&[0.0])).is_nan());
+    }
+
+    #[test]
+    fn top_k_accuracy_counts_second_best() {
+        // The target is the second-highest logit in the first sample and the
+        // highest in the second.
+        let preds = vec![values(&[0.1, 2.0, 1.5]), values(&[3.0, 0.0, 1.0])];
+        let targets = [2, 0];
+        assert_eq!(top_k_accuracy(&preds, &targets, 1), 0.5);
+        assert_eq!(top_k_accuracy(&preds, &targets, 2), 1.0);
+    }
+
+    #[test]
+    fn precision_recall_from_crafted_confusion() {
+        // Predictions (argmax): 0, 0, 1, 1, 0 against targets 0, 1, 1, 1, 2.
+        let preds = vec![
+            values(&[1.0, 0.0, 0.0]),
+            values(&[1.0, 0.0, 0.0]),
+            values(&[0.0, 1.0, 0.0]),
+            values(&[0.0, 1.0, 0.0]),
+            values(&[1.0, 0.0, 0.0]),
+        ];
+        let targets = [0, 1, 1, 1, 2];
+        let pr = precision_recall(&preds, &targets, 3);
+        assert_eq!(pr[0], (1.0 / 3.0, 1.0));
+        assert_eq!(pr[1], (1.0, 2.0 / 3.0));
+        // Class 2 is never predicted.
+        assert_eq!(pr[2], (0.0, 0.0));
+    }
+
+    #[test]
+    fn roc_auc_perfect_inverted_and_tied() {
+        let labels = [0, 0, 1, 1];
+        assert_eq!(roc_auc(&[0.1, 0.2, 0.8, 0.9], &labels), 1.0);
+        assert_eq!(roc_auc(&[0.9, 0.8, 0.2, 0.1], &labels), 0.0);
+        // All scores tied: every pair counts half.
+        assert_eq!(roc_auc(&[0.5; 4], &labels), 0.5);
+        // Positives at 0.35 and 0.8 against negatives at 0.1 and 0.4: 3 of 4 pairs ordered.
+        assert_eq!(roc_auc(&[0.1, 0.4, 0.35, 0.8], &labels), 0.75);
+        assert!(roc_auc(&[0.1, 0.2], &[1, 1]).is_nan());
+    }
+
+    #[test]
+    fn roc_auc_counts_ordered_pairs_with_distinct_scores() {
+        // Positives 0.9, 0.4, 0.1 against negatives 0.8, 0.5, 0.2: 0.9 beats
+        // all three and 0.4 beats 0.2, so 4 of 9 pairs are ordered.
+        let scores = [0.9, 0.8, 0.4, 0.2, 0.1, 0.5];
+        assert_eq!(roc_auc(&scores, &[1, 0, 1, 0, 1, 0]), 4.0 / 9.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "roc_auc: scores and labels must have equal length, got 3 and 2")]
+    fn roc_auc_rejects_mismatched_lengths() {
+        roc_auc(&[0.1, 0.2, 0.3], &[0, 1]);
+    }
+
+    #[test]
+    fn pr_auc_matches_hand_computed_average_precision() {
+        // Positives are found at ranks 1, 3 and 5, with precision 1, 2/3 and 3/5.
+        let ap = pr_auc(&[0.9, 0.8, 0.7, 0.6, 0.5], &[1, 0, 1, 0, 1]);
+        assert!((ap - (1.0 + 2.0 / 3.0 + 0.6) / 3.0).abs() < 1e-12);
+        assert_eq!(pr_auc(&[0.9, 0.8, 0.1], &[1, 1, 0]), 1.0);
+        assert!(pr_auc(&[0.5], &[0]).is_nan());
+    }
+
+    #[test]
+    fn best_threshold_f1_on_separable_scores() {
+        let (threshold, f1) = best_threshold_f1(&[0.1, 0.3, 0.6, 0.9], &[0, 0, 1, 1]);
+        assert_eq!(threshold, 0.6);
+        assert_eq!(f1, 1.0);
+    }
+}
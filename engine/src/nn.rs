@@ -0,0 +1,322 @@
+use crate::value::Value;
+use crate::{Layer, MLP};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+pub trait Module {
+    fn forward(&self, x: &[Value]) -> Vec<Value>;
+    fn parameters(&self) -> Vec<Value>;
+    fn zero_grad(&self);
+}
+
+impl Module for Layer {
+    fn forward(&self, x: &[Value]) -> Vec<Value> {
+        Layer::forward(self, x)
+    }
+
+    fn parameters(&self) -> Vec<Value> {
+        Layer::parameters(self)
+    }
+
+    fn zero_grad(&self) {
+        Layer::zero_grad(self)
+    }
+}
+
+impl Module for MLP {
+    fn forward(&self, x: &[Value]) -> Vec<Value> {
+        MLP::forward(self, x)
+    }
+
+    fn parameters(&self) -> Vec<Value> {
+        MLP::parameters(self)
+    }
+
+    fn zero_grad(&self) {
+        MLP::zero_grad(self)
+    }
+}
+
+// Runs each module on the previous one's output, in order.
+pub struct Sequential {
+    modules: Vec<Box<dyn Module>>,
+}
+
+impl Sequential {
+    pub fn new(modules: Vec<Box<dyn Module>>) -> Sequential {
+        Sequential { modules }
+    }
+}
+
+impl Module for Sequential {
+    fn forward(&self, x: &[Value]) -> Vec<Value> {
+        let mut out = x.to_vec();
+        for module in &self.modules {
+            out = module.forward(&out);
+        }
+        out
+    }
+
+    fn parameters(&self) -> Vec<Value> {
+        self.modules.iter().flat_map(|m| m.parameters()).collect()
+    }
+
+    fn zero_grad(&self) {
+        for module in &self.modules {
+            module.zero_grad();
+        }
+    }
+}
+
+// Elementwise activations as standalone modules, so a Sequential can put
+// one between layers built with Activation::Linear.
+pub struct Relu;
+
+impl Module for Relu {
+    fn forward(&self, x: &[Value]) -> Vec<Value> {
+        x.iter().map(|xi| xi.clone().relu()).collect()
+    }
+
+    fn parameters(&self) -> Vec<Value> {
+        Vec::new()
+    }
+
+    fn zero_grad(&self) {}
+}
+
+pub struct Tanh;
+
+impl Module for Tanh {
+    fn forward(&self, x: &[Value]) -> Vec<Value> {
+        x.iter().map(|xi| xi.clone().tanh()).collect()
+    }
+
+    fn parameters(&self) -> Vec<Value> {
+        Vec::new()
+    }
+
+    fn zero_grad(&self) {}
+}
+
+// Wraps a module with gradient checkpointing (see Value::checkpoint): its
+// internal activations are recomputed during backward instead of stored.
+// The wrapped module must be deterministic, so don't put Dropout inside.
+pub struct Checkpoint {
+    inner: Rc<dyn Module>,
+}
+
+impl Checkpoint {
+    pub fn new(module: impl Module + 'static) -> Checkpoint {
+        Checkpoint {
+            inner: Rc::new(module),
+        }
+    }
+}
+
+impl Module for Checkpoint {
+    fn forward(&self, x: &[Value]) -> Vec<Value> {
+        let inner = self.inner.clone();
+        Value::checkpoint(x, move |inputs| inner.forward(inputs))
+    }
+
+    fn parameters(&self) -> Vec<Value> {
+        self.inner.parameters()
+    }
+
+    fn zero_grad(&self) {
+        self.inner.zero_grad()
+    }
+}
+
+// Inverted dropout: kept inputs are scaled by 1/(1-p) during training so
+// evaluation can pass inputs through unchanged.
+pub struct Dropout {
+    p: f64,
+    training: Cell<bool>,
+    rng: RefCell<StdRng>,
+}
+
+impl Dropout {
+    pub fn new(p: f64, seed: u64) -> Dropout {
+        assert!(
+            (0.0..1.0).contains(&p),
+            "dropout probability must be in [0, 1), got {}",
+            p
+        );
+        Dropout {
+            p,
+            training: Cell::new(true),
+            rng: RefCell::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+
+    pub fn set_training(&self, training: bool) {
+        self.training.set(training);
+    }
+}
+
+impl Module for Dropout {
+    fn forward(&self, x: &[Value]) -> Vec<Value> {
+        if !self.training.get() || self.p == 0.0 {
+            return x.to_vec();
+        }
+        let scale = 1.0 / (1.0 - self.p);
+        let mut rng = self.rng.borrow_mut();
+        x.iter()
+            .map(|xi| {
+                let keep = rng.random::<f64>() >= self.p;
+                xi.clone() * if keep { scale } else { 0.0 }
+            })
+            .collect()
+    }
+
+    fn parameters(&self) -> Vec<Value> {
+        Vec::new()
+    }
+
+    fn zero_grad(&self) {}
+}
+
+// Normalizes across the features of a single sample, then applies a
+// learnable per-feature scale and shift.
+pub struct LayerNorm {
+    gamma: Vec<Value>,
+    beta: Vec<Value>,
+    eps: f64,
+}
+
+impl LayerNorm {
+    pub fn new(size: usize, eps: f64) -> LayerNorm {
+        LayerNorm {
+            gamma: (0..size).map(|_| Value::new(1.0)).collect(),
+            beta: (0..size).map(|_| Value::new(0.0)).collect(),
+            eps,
+        }
+    }
+}
+
+impl Module for LayerNorm {
+    fn forward(&self, x: &[Value]) -> Vec<Value> {
+        assert_eq!(
+            x.len(),
+            self.gamma.len(),
+            "expected {} inputs, got {}",
+            self.gamma.len(),
+            x.len()
+        );
+        let n = x.len() as f64;
+        let mut sum = Value::new(0.0);
+        for xi in x {
+            sum = sum + xi.clone();
+        }
+        let mean = sum * (1.0 / n);
+
+        let mut sq_sum = Value::new(0.0);
+        for xi in x {
+            let diff = xi.clone() - mean.clone();
+            sq_sum = sq_sum + diff.clone() * diff;
+        }
+        let inv_std = (sq_sum * (1.0 / n) + self.eps).pow(-0.5);
+
+        x.iter()
+            .zip(self.gamma.iter().zip(self.beta.iter()))
+            .map(|(xi, (g, b))| {
+                (xi.clone() - mean.clone()) * inv_std.clone() * g.clone() + b.clone()
+            })
+            .collect()
+    }
+
+    fn parameters(&self) -> Vec<Value> {
+        let mut params = self.gamma.clone();
+        params.extend(self.beta.iter().cloned());
+        params
+    }
+
+    fn zero_grad(&self) {
+        for p in self.parameters() {
+            p.zero_grad();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Activation;
+
+    fn inputs(xs: &[f64]) -> Vec<Value> {
+        xs.iter().map(|&x| Value::new(x)).collect()
+    }
+
+    fn data(xs: &[Value]) -> Vec<f64> {
+        xs.iter().map(|x| x.data()).collect()
+    }
+
+    #[test]
+    fn heterogeneous_pipeline_runs_each_module_in_turn() {
+        let layer = Layer::new(2, 3, Activation::Relu, 0.1);
+        let norm = LayerNorm::new(3, 1e-5);
+        let mlp = MLP::new(3, &[2]);
+        let x = inputs(&[0.5, -1.5]);
+        let expected = data(&mlp.forward(&norm.forward(&Module::forward(&layer, &x))));
+
+        let pipeline: Vec<Box<dyn Module>> = vec![Box::new(layer), Box::new(norm), Box::new(mlp)];
+        let mut out = x;
+        for module in &pipeline {
+            out = module.forward(&out);
+        }
+        assert_eq!(data(&out), expected);
+        let param_count: usize = pipeline.iter().map(|m| m.parameters().len()).sum();
+        assert_eq!(param_count, (2 * 3 + 3) + (3 + 3) + (3 * 2 + 2));
+    }
+
+    #[test]
+    fn sequential_chains_forward_and_collects_parameters() {
+        let first = Layer::new(2, 4, Activation::Relu, 0.1);
+        let last = Layer::new(4, 1, Activation::Linear, 0.0);
+        let x = inputs(&[0.5, -1.5]);
+        let expected = data(&Module::forward(&last, &Module::forward(&first, &x)));
+        let expected_params: Vec<f64> = first
+            .parameters()
+            .iter()
+            .chain(last.parameters().iter())
+            .map(|p| p.data())
+            .collect();
+
+        let dropout = Dropout::new(0.5, 0);
+        dropout.set_training(false);
+        let model = Sequential::new(vec![Box::new(first), Box::new(dropout), Box::new(last)]);
+        let out = model.forward(&x);
+        assert_eq!(data(&out), expected);
+        assert_eq!(data(&model.parameters()), expected_params);
+
+        out[0].backward();
+        assert!(model.parameters().iter().any(|p| p.grad() != 0.0));
+        model.zero_grad();
+        assert!(model.parameters().iter().all(|p| p.grad() == 0.0));
+    }
+
+    #[test]
+    fn sequential_composes_standalone_activations() {
+        assert_eq!(
+            data(&Relu.forward(&inputs(&[-1.0, 0.0, 2.0]))),
+            vec![0.0, 0.0, 2.0]
+        );
+        assert_eq!(
+            data(&Tanh.forward(&inputs(&[0.0, 1.0]))),
+            vec![0.0, 1.0f64.tanh()]
+        );
+
+        let first = Layer::new(2, 3, Activation::Linear, 0.1);
+        let last = Layer::new(3, 1, Activation::Linear, 0.0);
+        let x = inputs(&[0.5, -1.5]);
+        let hidden = Relu.forward(&Module::forward(&first, &x));
+        let expected = data(&Module::forward(&last, &hidden));
+        let param_count = first.parameters().len() + last.parameters().len();
+
+        let model = Sequential::new(vec![Box::new(first), Box::new(Relu), Box::new(last)]);
+        assert_eq!(data(&model.forward(&x)), expected);
+        assert_eq!(model.parameters().len(), param_count);
+    }
+}
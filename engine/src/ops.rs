@@ -0,0 +1,339 @@
+use crate::value::Value;
+
+// Left fold of f over values starting from init, e.g. a product with
+// init = Value::new(1.0) and f = |a, b| a * b. The result is one graph, so
+// gradients flow back to every element.
+pub fn reduce(values: &[Value], init: Value, f: impl Fn(Value, Value) -> Value) -> Value {
+    values.iter().fold(init, |acc, v| f(acc, v.clone()))
+}
+
+// The max is subtracted as a constant before exponentiating so large inputs
+// don't overflow; the gradient is unaffected since it cancels out.
+pub fn logsumexp(values: &[Value]) -> Value {
+    let max = values
+        .iter()
+        .map(|v| v.data())
+        .fold(f64::NEG_INFINITY, f64::max);
+    let mut sum = Value::new(0.0);
+    for v in values {
+        sum = sum + (v.clone() + Value::constant(-max)).exp();
+    }
+    sum.ln() + Value::constant(max)
+}
+
+fn dot(a: &[Value], b: &[Value]) -> Value {
+    let mut total = Value::new(0.0);
+    for (x, y) in a.iter().zip(b.iter()) {
+        total = total + x.clone() * y.clone();
+    }
+    total
+}
+
+// a.b / (|a| |b|). A product of norms below 1e-12 is replaced by a constant
+// 1e-12, so a zero vector gives 0 instead of NaN.
+pub fn cosine_similarity(a: &[Value], b: &[Value]) -> Value {
+    assert_eq!(
+        a.len(),
+        b.len(),
+        "cosine_similarity: slices must have equal length, got {} and {}",
+        a.len(),
+        b.len()
+    );
+    let norm_a = dot(a, a).pow(0.5);
+    let norm_b = dot(b, b).pow(0.5);
+    let norms = norm_a * norm_b;
+    let norms = if norms.data() < 1e-12 {
+        Value::constant(1e-12)
+    } else {
+        norms
+    };
+    dot(a, b) * norms.pow(-1.0)
+}
+
+// Normalizes each feature across the batch with the batch mean and biased
+// variance, then applies the per-feature scale gamma and shift beta.
+pub fn batch_norm(
+    batch: &[Vec<Value>],
+    gamma: &[Value],
+    beta: &[Value],
+    eps: f64,
+) -> Vec<Vec<Value>> {
+    let n = batch.len() as f64;
+    let nfeatures = gamma.len();
+    let mut out: Vec<Vec<Value>> = vec![Vec::with_capacity(nfeatures); batch.len()];
+
+    for j in 0..nfeatures {
+        let mut sum = Value::new(0.0);
+        for sample in batch {
+            sum = sum + sample[j].clone();
+        }
+        let mean = sum * (1.0 / n);
+
+        let mut sq_sum = Value::new(0.0);
+        for sample in batch {
+            let diff = sample[j].clone() - mean.clone();
+            sq_sum = sq_sum + diff.clone() * diff;
+        }
+        let inv_std = (sq_sum * (1.0 / n) + eps).pow(-0.5);
+
+        for (i, sample) in batch.iter().enumerate() {
+            let normalized = (sample[j].clone() - mean.clone()) * inv_std.clone();
+            out[i].push(normalized * gamma[j].clone() + beta[j].clone());
+        }
+    }
+    out
+}
+
+// Squashes v into [lo, hi] as lo + (hi - lo) * (tanh(v) + 1) / 2.
+pub fn bounded_output(v: &Value, lo: f64, hi: f64) -> Value {
+    (v.clone().tanh() + 1.0) * ((hi - lo) / 2.0) + lo
+}
+
+pub fn log_softmax(values: &[Value]) -> Vec<Value> {
+    let lse = logsumexp(values);
+    values.iter().map(|v| v.clone() - lse.clone()).collect()
+}
+
+pub fn softmax(values: &[Value]) -> Vec<Value> {
+    log_softmax(values).into_iter().map(|v| v.exp()).collect()
+}
+
+// sum(i * softmax(beta * values)_i): a differentiable stand-in for the argmax
+// index that approaches it as beta grows.
+pub fn soft_argmax(values: &[Value], beta: f64) -> Value {
+    let scaled: Vec<Value> = values.iter().map(|v| v.clone() * beta).collect();
+    let mut total = Value::new(0.0);
+    for (i, p) in softmax(&scaled).into_iter().enumerate() {
+        total = total + p * i as f64;
+    }
+    total
+}
+
+// -sum(p * ln(p)). Probabilities below 1e-12 use a constant 1e-12 inside
+// the log so zero entries contribute 0 instead of NaN.
+pub fn entropy(probs: &[Value]) -> Value {
+    let mut total = Value::new(0.0);
+    for p in probs {
+        let log_p = if p.data() < 1e-12 {
+            Value::constant(1e-12).ln()
+        } else {
+            p.clone().ln()
+        };
+        total = total + p.clone() * log_p;
+    }
+    total * -1.0
+}
+
+pub fn hadamard(a: &[Value], b: &[Value]) -> Vec<Value> {
+    assert_eq!(
+        a.len(),
+        b.len(),
+        "hadamard: slices must have equal length, got {} and {}",
+        a.len(),
+        b.len()
+    );
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| x.clone() * y.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(xs: &[f64]) -> Vec<Value> {
+        xs.iter().map(|&x| Value::new(x)).collect()
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn reduce_product_gradients() {
+        let xs = values(&[2.0, 3.0, 4.0]);
+        let product = reduce(&xs, Value::new(1.0), |a, b| a * b);
+        assert_close(product.data(), 24.0);
+        product.backward();
+        // d(xyz)/dx = yz and so on.
+        assert_close(xs[0].grad(), 12.0);
+        assert_close(xs[1].grad(), 8.0);
+        assert_close(xs[2].grad(), 6.0);
+    }
+
+    #[test]
+    fn logsumexp_matches_naive_and_stays_finite() {
+        let xs = values(&[0.5, -1.0, 2.0]);
+        let naive = xs.iter().map(|x| x.data().exp()).sum::<f64>().ln();
+        assert_close(logsumexp(&xs).data(), naive);
+
+        let large = values(&[1000.0, 1001.0]);
+        let lse = logsumexp(&large);
+        assert!(lse.data().is_finite());
+        assert_close(lse.data(), 1001.0 + (1.0 + (-1.0f64).exp()).ln());
+        lse.backward();
+        assert!(large.iter().all(|x| x.grad().is_finite()));
+    }
+
+    #[test]
+    fn bounded_output_saturates_and_centers() {
+        assert_close(bounded_output(&Value::new(0.0), -2.0, 4.0).data(), 1.0);
+        assert!((bounded_output(&Value::new(50.0), -2.0, 4.0).data() - 4.0).abs() < 1e-9);
+        assert!((bounded_output(&Value::new(-50.0), -2.0, 4.0).data() + 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hadamard_gradients() {
+        let a = values(&[1.0, 2.0]);
+        let b = values(&[3.0, -4.0]);
+        let out = hadamard(&a, &b);
+        (out[0].clone() + out[1].clone()).backward();
+        assert_close(out[1].data(), -8.0);
+        assert_close(a[0].grad(), 3.0);
+        assert_close(b[1].grad(), 2.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "hadamard: slices must have equal length")]
+    fn hadamard_rejects_mismatched_lengths() {
+        hadamard(&values(&[1.0, 2.0]), &values(&[1.0]));
+    }
+
+    #[test]
+    fn batch_norm_standardizes_each_feature() {
+        let batch: Vec<Vec<Value>> = [[1.0, 10.0], [2.0, -4.0], [3.0, 0.5], [6.0, 2.0]]
+            .iter()
+            .map(|r| values(r))
+            .collect();
+        let gamma = values(&[1.0, 1.0]);
+        let beta = values(&[0.0, 0.0]);
+        let out = batch_norm(&batch, &gamma, &beta, 1e-8);
+        for j in 0..2 {
+            let col: Vec<f64> = out.iter().map(|row| row[j].data()).collect();
+            let mean = col.iter().sum::<f64>() / 4.0;
+            let var = col.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / 4.0;
+            assert!(mean.abs() < 1e-9);
+            assert!((var - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn batch_norm_backward_matches_closed_form() {
+        let batch: Vec<Vec<Value>> = [[1.0, 10.0], [2.0, -4.0], [3.0, 0.5], [6.0, 2.0]]
+            .iter()
+            .map(|r| values(r))
+            .collect();
+        let gamma = values(&[2.0, 0.5]);
+        let beta = values(&[0.3, -1.0]);
+        let out = batch_norm(&batch, &gamma, &beta, 1e-8);
+
+        // Sample weights summing to the batch size, so each beta collects
+        // exactly 4 while gamma and the inputs get non-trivial grads.
+        let weights = [3.0, -1.0, 0.5, 1.5];
+        let mut loss = Value::new(0.0);
+        for (row, &w) in out.iter().zip(weights.iter()) {
+            for o in row {
+                loss = loss + o.clone() * w;
+            }
+        }
+        loss.backward();
+
+        for j in 0..2 {
+            assert_close(beta[j].grad(), 4.0);
+            let normalized: Vec<f64> = out
+                .iter()
+                .map(|row| (row[j].data() - beta[j].data()) / gamma[j].data())
+                .collect();
+            let expected: f64 = normalized
+                .iter()
+                .zip(weights.iter())
+                .map(|(n, w)| n * w)
+                .sum();
+            assert!((gamma[j].grad() - expected).abs() < 1e-6);
+            assert!(gamma[j].grad().abs() > 0.1);
+            // The mean is subtracted, so shifting a whole feature changes nothing.
+            let input_grad_sum: f64 = batch.iter().map(|row| row[j].grad()).sum();
+            assert!(input_grad_sum.abs() < 1e-6);
+            assert!(batch.iter().any(|row| row[j].grad().abs() > 1e-3));
+        }
+    }
+
+    #[test]
+    fn cosine_similarity_values_and_grads() {
+        assert_close(
+            cosine_similarity(&values(&[1.0, 2.0]), &values(&[1.0, 2.0])).data(),
+            1.0,
+        );
+        assert_close(
+            cosine_similarity(&values(&[1.0, 0.0]), &values(&[0.0, 3.0])).data(),
+            0.0,
+        );
+
+        // For unit a and b = (1, 0): d cos / d a = b - cos * a.
+        let a = values(&[0.6, 0.8]);
+        let b = values(&[1.0, 0.0]);
+        let cos = cosine_similarity(&a, &b);
+        cos.backward();
+        assert_close(a[0].grad(), 1.0 - 0.6 * 0.6);
+        assert_close(a[1].grad(), -0.6 * 0.8);
+    }
+
+    #[test]
+    fn cosine_similarity_of_zero_vector_is_zero() {
+        let a = values(&[0.0, 0.0]);
+        let b = values(&[1.0, 2.0]);
+        let cos = cosine_similarity(&a, &b);
+        assert_eq!(cos.data(), 0.0);
+        cos.backward();
+        assert!(a.iter().chain(b.iter()).all(|v| v.grad().is_finite()));
+    }
+
+    #[test]
+    fn soft_argmax_approaches_argmax_when_peaked() {
+        let logits = values(&[0.1, 3.0, 0.2]);
+        assert!((soft_argmax(&logits, 50.0).data() - 1.0).abs() < 1e-6);
+        // beta = 0 weights every index equally.
+        assert_close(soft_argmax(&logits, 0.0).data(), 1.0);
+        assert_close(soft_argmax(&values(&[0.0, 0.0, 5.0, 0.0]), 0.0).data(), 1.5);
+        assert!((soft_argmax(&values(&[0.0, 0.0, 5.0, 0.0]), 20.0).data() - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn entropy_of_uniform_and_one_hot() {
+        let uniform = values(&[0.25, 0.25, 0.25, 0.25]);
+        let h = entropy(&uniform);
+        assert_close(h.data(), 4f64.ln());
+        h.backward();
+        // d/dp of -p ln p is -(ln p + 1).
+        for p in &uniform {
+            assert_close(p.grad(), -(0.25f64.ln() + 1.0));
+        }
+
+        let one_hot = entropy(&values(&[0.0, 1.0, 0.0]));
+        assert_close(one_hot.data(), 0.0);
+        assert!(!one_hot.data().is_nan());
+    }
+
+    #[test]
+    fn entropy_ascent_moves_mass_to_rare_entries() {
+        let probs = values(&[0.7, 0.2, 0.1]);
+        let h = entropy(&probs);
+        h.backward();
+        let grads: Vec<f64> = probs.iter().map(|p| p.grad()).collect();
+        for (p, g) in probs.iter().zip(grads.iter()) {
+            assert_close(*g, -(p.data().ln() + 1.0));
+        }
+        assert!(grads[2] > grads[1] && grads[1] > grads[0]);
+
+        // Removing the mean of the grads keeps the step on the simplex.
+        let mean = grads.iter().sum::<f64>() / 3.0;
+        let stepped: Vec<f64> = probs
+            .iter()
+            .zip(grads.iter())
+            .map(|(p, g)| p.data() + 0.05 * (g - mean))
+            .collect();
+        assert_close(stepped.iter().sum::<f64>(), 1.0);
+        assert!(entropy(&values(&stepped)).data() > h.data());
+    }
+}
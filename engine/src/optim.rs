@@ -0,0 +1,560 @@
+use crate::MLP;
+use crate::data::RunningStats;
+use crate::value::Value;
+use rand::{SeedableRng, rngs::StdRng};
+use rand_distr::{Distribution, Normal};
+use std::collections::VecDeque;
+
+pub struct SGD {
+    params: Vec<Value>,
+    lr: f64,
+    min_lr: f64,
+    max_lr: f64,
+    t: usize,
+}
+
+impl SGD {
+    // The learning rate adapted by step_scaled is kept within
+    // [lr / 1000, lr * 10] unless overridden with with_lr_bounds.
+    pub fn new(params: Vec<Value>, lr: f64) -> SGD {
+        SGD {
+            params,
+            lr,
+            min_lr: lr / 1000.0,
+            max_lr: lr * 10.0,
+            t: 0,
+        }
+    }
+
+    pub fn with_lr_bounds(mut self, min_lr: f64, max_lr: f64) -> SGD {
+        self.min_lr = min_lr;
+        self.max_lr = max_lr;
+        self
+    }
+
+    pub fn lr(&self) -> f64 {
+        self.lr
+    }
+
+    // Frozen parameters (requires_grad false) are left untouched.
+    pub fn step(&mut self) {
+        self.t += 1;
+        for param in &self.params {
+            if param.requires_grad() {
+                param.update(self.lr);
+            }
+        }
+    }
+
+    pub fn zero_grad(&self) {
+        for param in &self.params {
+            param.zero_grad();
+        }
+    }
+
+    // Bold-driver heuristic: halve the learning rate if the loss went up,
+    // grow it by 5% if it went down, then take a regular step.
+    pub fn step_scaled(&mut self, loss: f64, prev_loss: f64) {
+        if loss > prev_loss {
+            self.lr *= 0.5;
+        } else {
+            self.lr *= 1.05;
+        }
+        self.lr = self.lr.clamp(self.min_lr, self.max_lr);
+        self.step();
+    }
+
+    // Laid out as [step count, learning rate]. Plain SGD keeps no momentum
+    // buffers, so the learning rate adapted by step_scaled is the only other
+    // state.
+    pub fn state(&self) -> Vec<f64> {
+        vec![self.t as f64, self.lr]
+    }
+
+    pub fn load_state(&mut self, state: &[f64]) {
+        assert_eq!(
+            state.len(),
+            2,
+            "expected SGD state of length 2, got {}",
+            state.len()
+        );
+        self.t = state[0] as usize;
+        self.lr = state[1];
+    }
+}
+
+pub struct Adam {
+    params: Vec<Value>,
+    lr: f64,
+    beta1: f64,
+    beta2: f64,
+    eps: f64,
+    m: Vec<f64>,
+    v: Vec<f64>,
+    t: usize,
+}
+
+impl Adam {
+    // Uses the usual defaults beta1 = 0.9, beta2 = 0.999, eps = 1e-8.
+    pub fn new(params: Vec<Value>, lr: f64) -> Adam {
+        let n = params.len();
+        Adam {
+            params,
+            lr,
+            beta1: 0.9,
+            beta2: 0.999,
+            eps: 1e-8,
+            m: vec![0.0; n],
+            v: vec![0.0; n],
+            t: 0,
+        }
+    }
+
+    // Frozen parameters (requires_grad false) are left untouched.
+    pub fn step(&mut self) {
+        self.t += 1;
+        let bias1 = 1.0 - self.beta1.powi(self.t as i32);
+        let bias2 = 1.0 - self.beta2.powi(self.t as i32);
+        for ((param, m), v) in self
+            .params
+            .iter()
+            .zip(self.m.iter_mut())
+            .zip(self.v.iter_mut())
+        {
+            if !param.requires_grad() {
+                continue;
+            }
+            let g = param.grad();
+            *m = self.beta1 * *m + (1.0 - self.beta1) * g;
+            *v = self.beta2 * *v + (1.0 - self.beta2) * g * g;
+            let m_hat = *m / bias1;
+            let v_hat = *v / bias2;
+            param.set_data(param.data() - self.lr * m_hat / (v_hat.sqrt() + self.eps));
+        }
+    }
+
+    pub fn zero_grad(&self) {
+        for param in &self.params {
+            param.zero_grad();
+        }
+    }
+
+    // Laid out as [step count, first moments..., second moments...].
+    pub fn state(&self) -> Vec<f64> {
+        let mut state = Vec::with_capacity(1 + 2 * self.params.len());
+        state.push(self.t as f64);
+        state.extend_from_slice(&self.m);
+        state.extend_from_slice(&self.v);
+        state
+    }
+
+    pub fn load_state(&mut self, state: &[f64]) {
+        let n = self.params.len();
+        assert_eq!(
+            state.len(),
+            1 + 2 * n,
+            "expected Adam state of length {}, got {}",
+            1 + 2 * n,
+            state.len()
+        );
+        self.t = state[0] as usize;
+        self.m = state[1..1 + n].to_vec();
+        self.v = state[1 + n..].to_vec();
+    }
+}
+
+// Ramps the learning rate linearly from 0 to target_lr over the first
+// warmup_steps steps, then holds it at target_lr.
+pub struct WarmupLR {
+    target_lr: f64,
+    warmup_steps: usize,
+}
+
+impl WarmupLR {
+    pub fn new(target_lr: f64, warmup_steps: usize) -> WarmupLR {
+        WarmupLR {
+            target_lr,
+            warmup_steps,
+        }
+    }
+
+    pub fn lr(&self, step: usize) -> f64 {
+        if step >= self.warmup_steps {
+            self.target_lr
+        } else {
+            self.target_lr * step as f64 / self.warmup_steps as f64
+        }
+    }
+}
+
+// Adds independent N(0, std^2) noise to every parameter's grad, drawn from a
+// seeded RNG so runs are reproducible. Call between backward and the step.
+pub fn add_grad_noise(params: &[Value], std: f64, seed: u64) {
+    if std == 0.0 {
+        return;
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let normal = Normal::new(0.0, std).expect("std must be finite and non-negative");
+    for param in params {
+        param.set_grad(param.grad() + normal.sample(&mut rng));
+    }
+}
+
+pub fn has_nan_grad(params: &[Value]) -> bool {
+    params.iter().any(|p| !p.grad().is_finite())
+}
+
+pub fn has_nan_data(params: &[Value]) -> bool {
+    params.iter().any(|p| !p.data().is_finite())
+}
+
+// Variance across samples of each parameter's squared-error gradient,
+// averaged over parameters. High values mean noisy minibatch gradients.
+// Parameter grads are left zeroed.
+pub fn grad_variance(mlp: &MLP, xs: &[Vec<Value>], ys: &[Value]) -> f64 {
+    let params = mlp.parameters();
+    let mut stats = RunningStats::new();
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        mlp.zero_grad();
+        let diff = mlp.forward(x)[0].clone() - y.clone();
+        (diff.clone() * diff).backward();
+        stats.update(&params.iter().map(|p| p.grad()).collect::<Vec<f64>>());
+    }
+    mlp.zero_grad();
+    stats.variance().iter().sum::<f64>() / params.len() as f64
+}
+
+// Triangular cyclic schedule: rises linearly from base_lr to max_lr over
+// step_size steps, falls back to base_lr over the next step_size, and repeats.
+pub struct CyclicLR {
+    base_lr: f64,
+    max_lr: f64,
+    step_size: usize,
+}
+
+impl CyclicLR {
+    pub fn new(base_lr: f64, max_lr: f64, step_size: usize) -> CyclicLR {
+        assert!(step_size > 0, "CyclicLR step_size must be positive");
+        CyclicLR {
+            base_lr,
+            max_lr,
+            step_size,
+        }
+    }
+
+    // Steps in one full cycle; the rate is at base_lr on every multiple of this.
+    pub fn cycle_length(&self) -> usize {
+        2 * self.step_size
+    }
+
+    pub fn lr(&self, step: usize) -> f64 {
+        let pos = step % (2 * self.step_size);
+        let dist = if pos < self.step_size {
+            pos
+        } else {
+            2 * self.step_size - pos
+        };
+        self.base_lr + (self.max_lr - self.base_lr) * dist as f64 / self.step_size as f64
+    }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+// Limited-memory BFGS on flat parameter vectors, e.g. from
+// MLP::parameters_flat and MLP::grad_flat, with a fixed step length instead
+// of a line search. Pairs that fail the curvature condition s.y > 0 are
+// dropped so the implied inverse Hessian stays positive definite.
+pub struct LBFGS {
+    lr: f64,
+    history_size: usize,
+    s_history: VecDeque<Vec<f64>>,
+    y_history: VecDeque<Vec<f64>>,
+    prev: Option<(Vec<f64>, Vec<f64>)>,
+}
+
+impl LBFGS {
+    pub fn new(lr: f64, history_size: usize) -> LBFGS {
+        LBFGS {
+            lr,
+            history_size,
+            s_history: VecDeque::new(),
+            y_history: VecDeque::new(),
+            prev: None,
+        }
+    }
+
+    // Returns the updated parameters; write them back with set_parameters_flat.
+    pub fn step(&mut self, params: &[f64], grads: &[f64]) -> Vec<f64> {
+        if let Some((prev_params, prev_grads)) = self.prev.take() {
+            let s: Vec<f64> = params
+                .iter()
+                .zip(prev_params.iter())
+                .map(|(p, q)| p - q)
+                .collect();
+            let y: Vec<f64> = grads
+                .iter()
+                .zip(prev_grads.iter())
+                .map(|(g, h)| g - h)
+                .collect();
+            if dot(&s, &y) > 1e-10 {
+                if self.s_history.len() == self.history_size {
+                    self.s_history.pop_front();
+                    self.y_history.pop_front();
+                }
+                self.s_history.push_back(s);
+                self.y_history.push_back(y);
+            }
+        }
+
+        // Two-loop recursion computing H * grad.
+        let mut q = grads.to_vec();
+        let mut alphas = Vec::with_capacity(self.s_history.len());
+        for (s, y) in self.s_history.iter().zip(self.y_history.iter()).rev() {
+            let rho = 1.0 / dot(y, s);
+            let alpha = rho * dot(s, &q);
+            for (qi, yi) in q.iter_mut().zip(y.iter()) {
+                *qi -= alpha * yi;
+            }
+            alphas.push((rho, alpha));
+        }
+        if let (Some(s), Some(y)) = (self.s_history.back(), self.y_history.back()) {
+            let gamma = dot(s, y) / dot(y, y);
+            for qi in q.iter_mut() {
+                *qi *= gamma;
+            }
+        }
+        for ((s, y), (rho, alpha)) in self
+            .s_history
+            .iter()
+            .zip(self.y_history.iter())
+            .zip(alphas.into_iter().rev())
+        {
+            let beta = rho * dot(y, &q);
+            for (qi, si) in q.iter_mut().zip(s.iter()) {
+                *qi += (alpha - beta) * si;
+            }
+        }
+
+        let next: Vec<f64> = params
+            .iter()
+            .zip(q.iter())
+            .map(|(p, d)| p - self.lr * d)
+            .collect();
+        self.prev = Some((params.to_vec(), grads.to_vec()));
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warmup_ramps_linearly_to_target() {
+        let schedule = WarmupLR::new(0.1, 10);
+        assert_eq!(schedule.lr(0), 0.0);
+        assert!((schedule.lr(5) - 0.05).abs() < 1e-12);
+        assert_eq!(schedule.lr(10), 0.1);
+        assert_eq!(schedule.lr(50), 0.1);
+    }
+
+    #[test]
+    fn nan_checks_detect_injected_values() {
+        let params = vec![Value::new(1.0), Value::new(2.0)];
+        assert!(!has_nan_grad(&params));
+        assert!(!has_nan_data(&params));
+
+        (params[1].clone() * Value::new(f64::NAN)).backward();
+        assert!(has_nan_grad(&params));
+        params[0].set_data(f64::INFINITY);
+        assert!(has_nan_data(&params));
+    }
+
+    #[test]
+    fn step_scaled_adapts_within_bounds() {
+        let w = Value::new(0.0);
+        w.set_grad(1.0);
+        let mut sgd = SGD::new(vec![w.clone()], 0.1).with_lr_bounds(0.02, 0.11);
+        // Each step moves w by the rescaled rate, not the previous one.
+        sgd.step_scaled(2.0, 1.0);
+        assert!((sgd.lr() - 0.05).abs() < 1e-12);
+        assert!((w.data() + 0.05).abs() < 1e-12);
+        sgd.step_scaled(1.0, 2.0);
+        assert!((sgd.lr() - 0.0525).abs() < 1e-12);
+        assert!((w.data() + 0.05 + 0.0525).abs() < 1e-12);
+        for _ in 0..5 {
+            sgd.step_scaled(2.0, 1.0);
+        }
+        assert_eq!(sgd.lr(), 0.02);
+        for _ in 0..50 {
+            sgd.step_scaled(1.0, 2.0);
+        }
+        assert_eq!(sgd.lr(), 0.11);
+    }
+
+    #[test]
+    fn sgd_state_round_trips_step_count_and_lr() {
+        let mut sgd = SGD::new(vec![Value::new(0.0)], 0.1);
+        sgd.step();
+        sgd.step_scaled(2.0, 1.0);
+        assert_eq!(sgd.state(), vec![2.0, 0.05]);
+
+        let mut restored = SGD::new(vec![Value::new(0.0)], 0.1);
+        restored.load_state(&sgd.state());
+        assert_eq!(restored.state(), sgd.state());
+        assert_eq!(restored.lr(), 0.05);
+    }
+
+    #[test]
+    fn cyclic_lr_full_cycle() {
+        let schedule = CyclicLR::new(0.01, 0.05, 4);
+        let rates: Vec<f64> = (0..=8).map(|step| schedule.lr(step)).collect();
+        let expected = [0.01, 0.02, 0.03, 0.04, 0.05, 0.04, 0.03, 0.02, 0.01];
+        for (r, e) in rates.iter().zip(expected.iter()) {
+            assert!((r - e).abs() < 1e-12);
+        }
+        assert_eq!(schedule.lr(3), schedule.lr(11));
+        assert_eq!(schedule.cycle_length(), 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "step_size must be positive")]
+    fn cyclic_lr_rejects_zero_step_size() {
+        CyclicLR::new(0.01, 0.05, 0);
+    }
+
+    #[test]
+    fn grad_noise_is_reproducible_per_seed() {
+        let grads_after = |std: f64, seed: u64| {
+            let params: Vec<Value> = (0..4).map(|i| Value::new(i as f64)).collect();
+            for (i, p) in params.iter().enumerate() {
+                p.set_grad(0.5 * i as f64);
+            }
+            add_grad_noise(&params, std, seed);
+            params.iter().map(|p| p.grad()).collect::<Vec<f64>>()
+        };
+        let clean = vec![0.0, 0.5, 1.0, 1.5];
+        assert_eq!(grads_after(0.0, 7), clean);
+
+        let noisy = grads_after(0.1, 7);
+        assert_ne!(noisy, clean);
+        assert_eq!(noisy, grads_after(0.1, 7));
+        assert_ne!(noisy, grads_after(0.1, 8));
+    }
+
+    #[test]
+    fn lbfgs_beats_sgd_on_linear_sum() {
+        use crate::loss::batch_loss;
+        use crate::{Activation, MLP};
+
+        // y = x1 + x2
+        let points = [
+            [0.0, 0.0],
+            [0.0, 1.0],
+            [1.0, 0.0],
+            [1.0, 1.0],
+            [0.5, 0.5],
+            [0.2, 0.9],
+        ];
+        let xs: Vec<Vec<Value>> = points
+            .iter()
+            .map(|p| p.iter().map(|&x| Value::new(x)).collect())
+            .collect();
+        let ys: Vec<Value> = points.iter().map(|p| Value::new(p[0] + p[1])).collect();
+        let model = || {
+            MLP::from_weights(
+                vec![vec![vec![0.0, 0.0]]],
+                vec![vec![0.0]],
+                vec![Activation::Linear],
+            )
+        };
+        let steps = 10;
+
+        let sgd = model();
+        for _ in 0..steps {
+            sgd.zero_grad();
+            batch_loss(&sgd, &xs, &ys).backward();
+            sgd.parameters().iter().for_each(|p| p.update(0.5));
+        }
+
+        let quasi_newton = model();
+        let mut lbfgs = LBFGS::new(0.5, 5);
+        for _ in 0..steps {
+            quasi_newton.zero_grad();
+            batch_loss(&quasi_newton, &xs, &ys).backward();
+            let next = lbfgs.step(&quasi_newton.parameters_flat(), &quasi_newton.grad_flat());
+            quasi_newton.set_parameters_flat(&next);
+        }
+
+        let sgd_loss = batch_loss(&sgd, &xs, &ys).data();
+        let lbfgs_loss = batch_loss(&quasi_newton, &xs, &ys).data();
+        assert!(
+            lbfgs_loss < sgd_loss / 10.0,
+            "L-BFGS {} vs SGD {}",
+            lbfgs_loss,
+            sgd_loss
+        );
+    }
+
+    #[test]
+    fn grad_variance_of_identical_samples_is_zero() {
+        use crate::MLP;
+
+        let mlp = MLP::new(2, &[4, 1]);
+        let x = vec![Value::new(0.3), Value::new(0.8)];
+        let xs = vec![x.clone(); 50];
+        let ys = vec![Value::new(1.1); 50];
+        assert!(grad_variance(&mlp, &xs, &ys).abs() < 1e-12);
+
+        let spread: Vec<Vec<Value>> = (0..10)
+            .map(|i| vec![Value::new(i as f64 * 0.1), Value::new(0.8)])
+            .collect();
+        let targets: Vec<Value> = (0..10).map(|i| Value::new(i as f64)).collect();
+        assert!(grad_variance(&mlp, &spread, &targets) > 0.0);
+        assert!(mlp.grad_flat().iter().all(|&g| g == 0.0));
+    }
+
+    #[test]
+    fn adam_resumes_from_saved_state() {
+        use crate::MLP;
+        use crate::loss::batch_loss;
+
+        let points = [[0.0, 1.0], [1.0, 0.0], [0.5, 0.5], [0.2, 0.9]];
+        let xs: Vec<Vec<Value>> = points
+            .iter()
+            .map(|p| p.iter().map(|&x| Value::new(x)).collect())
+            .collect();
+        let ys: Vec<Value> = points.iter().map(|p| Value::new(p[0] + p[1])).collect();
+        let train = |mlp: &MLP, adam: &mut Adam, steps: usize| {
+            for _ in 0..steps {
+                adam.zero_grad();
+                batch_loss(mlp, &xs, &ys).backward();
+                adam.step();
+            }
+        };
+
+        let original = MLP::new(2, &[4, 1]);
+        let mut adam = Adam::new(original.parameters(), 0.01);
+        train(&original, &mut adam, 3);
+        let saved_state = adam.state();
+        let resumed = original.deep_clone();
+        train(&original, &mut adam, 2);
+
+        let mut restored = Adam::new(resumed.parameters(), 0.01);
+        restored.load_state(&saved_state);
+        train(&resumed, &mut restored, 2);
+        assert_eq!(resumed.parameters_flat(), original.parameters_flat());
+        assert_eq!(restored.state(), adam.state());
+
+        // A fresh optimizer takes different steps.
+        let fresh = original.deep_clone();
+        let mut fresh_adam = Adam::new(fresh.parameters(), 0.01);
+        train(&fresh, &mut fresh_adam, 1);
+        let mut resumed_adam = Adam::new(original.parameters(), 0.01);
+        resumed_adam.load_state(&adam.state());
+        train(&original, &mut resumed_adam, 1);
+        assert_ne!(fresh.parameters_flat(), original.parameters_flat());
+    }
+}
@@ -0,0 +1,38 @@
+// Compensated (Kahan) summation, which carries the low-order bits lost by
+// each addition forward so long sums of small values don't drift.
+pub fn kahan_sum(values: &[f64]) -> f64 {
+    let mut sum = 0.0;
+    let mut compensation = 0.0;
+    for &v in values {
+        let y = v - compensation;
+        let t = sum + y;
+        compensation = (t - sum) - y;
+        sum = t;
+    }
+    sum
+}
+
+// -1, 0 or 1. Unlike f64::signum, zero maps to 0.
+pub(crate) fn sign(x: f64) -> f64 {
+    if x > 0.0 {
+        1.0
+    } else if x < 0.0 {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kahan_sum_avoids_drift() {
+        let values = vec![0.1; 1_000_000];
+        let naive: f64 = values.iter().sum();
+        let compensated = kahan_sum(&values);
+        assert!((compensated - 100_000.0).abs() < 1e-9);
+        assert!((naive - 100_000.0).abs() > (compensated - 100_000.0).abs());
+    }
+}
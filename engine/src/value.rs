@@ -1,17 +1,63 @@
-use std::ops::{Add, Mul, Sub};
-use std::rc::Rc;
+use crate::util::sign;
 use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::collections::HashSet;
+use std::ops::{Add, Mul, Sub};
+use std::rc::Rc;
 
 enum Op {
     Add,
     Mul,
     Pow(f64),
     Relu,
+    Exp,
+    Log,
+    Tanh,
+    SignSte,
+    // Output `index` of a sub-graph that is rebuilt from its inputs whenever
+    // it is needed instead of being kept alive.
+    Checkpoint(CheckpointFn, usize),
+}
+
+type CheckpointFn = Rc<dyn Fn(&[Value]) -> Vec<Value>>;
+
+fn detach(inputs: &[Value]) -> Vec<Value> {
+    inputs.iter().map(|x| Value::new(x.data())).collect()
 }
 
 impl Op {
+    fn name(&self) -> &'static str {
+        match self {
+            Op::Add => "Add",
+            Op::Mul => "Mul",
+            Op::Pow(_) => "Pow",
+            Op::Relu => "Relu",
+            Op::Exp => "Exp",
+            Op::Log => "Log",
+            Op::Tanh => "Tanh",
+            Op::SignSte => "SignSte",
+            Op::Checkpoint(..) => "Checkpoint",
+        }
+    }
+
+    fn forward(&self, inputs: &[Value]) -> f64 {
+        if let Op::Checkpoint(f, index) = self {
+            return f(&detach(inputs))[*index].data();
+        }
+        let x = inputs[0].data.borrow().data;
+        match self {
+            Op::Add => x + inputs[1].data.borrow().data,
+            Op::Mul => x * inputs[1].data.borrow().data,
+            Op::Pow(exponent) => x.powf(*exponent),
+            Op::Relu => x.max(0.0),
+            Op::Exp => x.exp(),
+            Op::Log => x.ln(),
+            Op::Tanh => x.tanh(),
+            Op::SignSte => sign(x),
+            Op::Checkpoint(..) => unreachable!(),
+        }
+    }
+
     fn backward(&self, out_grad: f64, inputs: &[Value]) -> Vec<f64> {
         match self {
             Op::Add => vec![out_grad, out_grad],
@@ -21,6 +67,10 @@ impl Op {
                 vec![out_grad * right, out_grad * left]
             }
             Op::Pow(exponent) => {
+                // x^0 is constant, so skip base^-1 which is infinite at 0.
+                if *exponent == 0.0 {
+                    return vec![0.0];
+                }
                 let base = inputs[0].data.borrow().data;
                 vec![out_grad * exponent * base.powf(exponent - 1.0)]
             }
@@ -29,6 +79,29 @@ impl Op {
                 let grad = if input_data > 0.0 { out_grad } else { 0.0 };
                 vec![grad]
             }
+            Op::Exp => {
+                let input_data = inputs[0].data.borrow().data;
+                vec![out_grad * input_data.exp()]
+            }
+            Op::Log => {
+                let input_data = inputs[0].data.borrow().data;
+                vec![out_grad / input_data]
+            }
+            Op::Tanh => {
+                let t = inputs[0].data.borrow().data.tanh();
+                vec![out_grad * (1.0 - t * t)]
+            }
+            // Straight-through estimator: the forward pass takes the sign but
+            // the gradient is passed through unchanged.
+            Op::SignSte => vec![out_grad],
+            // Replays the sub-graph on detached copies of the inputs and
+            // backpropagates through it; parameters captured by f receive
+            // their grads along the way.
+            Op::Checkpoint(f, index) => {
+                let detached = detach(inputs);
+                f(&detached)[*index].backward_from(out_grad);
+                detached.iter().map(|x| x.grad()).collect()
+            }
         }
     }
 }
@@ -41,6 +114,7 @@ pub struct Value {
 struct ValueData {
     data: f64,
     grad: f64,
+    requires_grad: bool,
     _op: Option<Op>,
     _prev: Option<Vec<Value>>,
 }
@@ -51,12 +125,21 @@ impl Value {
             data: Rc::new(RefCell::new(ValueData {
                 data,
                 grad: 0.0,
+                requires_grad: true,
                 _op: None,
                 _prev: None,
             })),
         }
     }
 
+    // A non-trainable literal: backward never accumulates into it, and
+    // optimizers can check requires_grad() to skip it.
+    pub fn constant(data: f64) -> Self {
+        let out = Value::new(data);
+        out.data.borrow_mut().requires_grad = false;
+        out
+    }
+
     pub fn pow(self, exponent: f64) -> Value {
         let out = Value::new(self.data.borrow().data.powf(exponent));
         out.data.borrow_mut()._op = Some(Op::Pow(exponent));
@@ -71,6 +154,27 @@ impl Value {
         out
     }
 
+    pub fn exp(self) -> Value {
+        let out = Value::new(self.data.borrow().data.exp());
+        out.data.borrow_mut()._op = Some(Op::Exp);
+        out.data.borrow_mut()._prev = Some(vec![self.clone()]);
+        out
+    }
+
+    pub fn ln(self) -> Value {
+        let out = Value::new(self.data.borrow().data.ln());
+        out.data.borrow_mut()._op = Some(Op::Log);
+        out.data.borrow_mut()._prev = Some(vec![self.clone()]);
+        out
+    }
+
+    pub fn tanh(self) -> Value {
+        let out = Value::new(self.data.borrow().data.tanh());
+        out.data.borrow_mut()._op = Some(Op::Tanh);
+        out.data.borrow_mut()._prev = Some(vec![self.clone()]);
+        out
+    }
+
     fn build_topo(node: Value, visited: &mut HashSet<*const RefCell<ValueData>>, topo: &mut Vec<Value>) {
         let node_ptr = Rc::as_ptr(&node.data);
         if !visited.contains(&node_ptr) {
@@ -85,25 +189,93 @@ impl Value {
     }
 
     pub fn backward(&self) {
-        self.data.borrow_mut().grad = 1.0;
+        self.backward_from(1.0);
+    }
+
+    // Like backward, but seeds the root gradient with an arbitrary value,
+    // which gives vector-Jacobian products when chaining graphs.
+    pub fn backward_from(&self, seed: f64) {
+        self.data.borrow_mut().grad = seed;
         let mut topo: Vec<Value> = Vec::new();
         let mut visited: HashSet<*const RefCell<ValueData>> = HashSet::new();
         Value::build_topo(self.clone(), &mut visited, &mut topo);
         topo.reverse();
 
         for node in topo {
-            if let Some(ref op) = node.data.borrow()._op {
-                if let Some(ref parents) = node.data.borrow()._prev {
-                    let out_grad = node.data.borrow().grad;
-                    let input_grads = op.backward(out_grad, parents);
-                    for (parent, grad) in parents.iter().zip(input_grads.iter()) {
-                        parent.data.borrow_mut().grad += *grad;
+            node.propagate_grad();
+        }
+    }
+
+    // Pushes this node's grad into its parents according to its op.
+    fn propagate_grad(&self) {
+        self.propagate_grad_where(&|_| true);
+    }
+
+    // Like propagate_grad, but only parents accepted by keep receive gradient.
+    fn propagate_grad_where(&self, keep: &dyn Fn(&Value) -> bool) {
+        if let Some(ref op) = self.data.borrow()._op {
+            if let Some(ref parents) = self.data.borrow()._prev {
+                let out_grad = self.data.borrow().grad;
+                let input_grads = op.backward(out_grad, parents);
+                for (parent, grad) in parents.iter().zip(input_grads.iter()) {
+                    if !keep(parent) {
+                        continue;
+                    }
+                    let mut parent_data = parent.data.borrow_mut();
+                    if parent_data.requires_grad {
+                        parent_data.grad += *grad;
                     }
                 }
             }
         }
     }
 
+    pub fn compile(&self, inputs: &[Value]) -> CompiledGraph {
+        CompiledGraph::new(self, inputs)
+    }
+
+    // Recomputes this node's data from its parents' current data.
+    fn recompute(&self) {
+        let data = match (&self.data.borrow()._op, &self.data.borrow()._prev) {
+            (Some(op), Some(parents)) => op.forward(parents),
+            _ => return,
+        };
+        self.data.borrow_mut().data = data;
+    }
+
+    // Backpropagates only through nodes that lie on a path to one of the
+    // targets; branches that can't reach a target are skipped and their
+    // grads are left untouched.
+    pub fn backward_to(&self, targets: &[Value]) {
+        let mut topo: Vec<Value> = Vec::new();
+        let mut visited: HashSet<*const RefCell<ValueData>> = HashSet::new();
+        Value::build_topo(self.clone(), &mut visited, &mut topo);
+
+        // topo lists parents before children, so one pass marks every node
+        // that has a target among its ancestors (or is one).
+        let mut relevant: HashSet<*const RefCell<ValueData>> =
+            targets.iter().map(|t| Rc::as_ptr(&t.data)).collect();
+        for node in &topo {
+            let reaches_target = match node.data.borrow()._prev {
+                Some(ref parents) => parents
+                    .iter()
+                    .any(|p| relevant.contains(&Rc::as_ptr(&p.data))),
+                None => false,
+            };
+            if reaches_target {
+                relevant.insert(Rc::as_ptr(&node.data));
+            }
+        }
+
+        self.data.borrow_mut().grad = 1.0;
+        let keep = |v: &Value| relevant.contains(&Rc::as_ptr(&v.data));
+        for node in topo.iter().rev() {
+            if keep(node) {
+                node.propagate_grad_where(&keep);
+            }
+        }
+    }
+
     fn print_all_grads(&self) {
         let mut topo: Vec<Value> = Vec::new();
         let mut visited: HashSet<*const RefCell<ValueData>> = HashSet::new();
@@ -115,6 +287,118 @@ impl Value {
         }
     }
 
+    // Forward returns -1, 0 or 1; backward treats the op as the identity.
+    pub fn sign_ste(self) -> Value {
+        let out = Value::new(sign(self.data.borrow().data));
+        out.data.borrow_mut()._op = Some(Op::SignSte);
+        out.data.borrow_mut()._prev = Some(vec![self.clone()]);
+        out
+    }
+
+    // Gradient checkpointing: runs f on detached copies of inputs and keeps
+    // only its output values, so f's intermediate nodes are freed right away.
+    // During backward f is run again for each output to recover gradients,
+    // trading compute for memory: with N outputs that reach the loss, one
+    // backward costs N extra evaluations of f.
+    pub fn checkpoint(
+        inputs: &[Value],
+        f: impl Fn(&[Value]) -> Vec<Value> + 'static,
+    ) -> Vec<Value> {
+        let f: CheckpointFn = Rc::new(f);
+        let outputs: Vec<f64> = f(&detach(inputs)).iter().map(|v| v.data()).collect();
+        outputs
+            .into_iter()
+            .enumerate()
+            .map(|(index, data)| {
+                let out = Value::new(data);
+                out.data.borrow_mut()._op = Some(Op::Checkpoint(f.clone(), index));
+                out.data.borrow_mut()._prev = Some(inputs.to_vec());
+                out
+            })
+            .collect()
+    }
+
+    fn depth(node: &Value, memo: &mut HashMap<*const RefCell<ValueData>, usize>) -> usize {
+        let node_ptr = Rc::as_ptr(&node.data);
+        if let Some(&depth) = memo.get(&node_ptr) {
+            return depth;
+        }
+        let mut depth = 0;
+        if let Some(ref parents) = node.data.borrow()._prev {
+            for parent in parents {
+                depth = depth.max(Value::depth(parent, memo) + 1);
+            }
+        }
+        memo.insert(node_ptr, depth);
+        depth
+    }
+
+    // Number of ops on the longest path from any leaf to this node; a leaf has depth 0.
+    pub fn graph_depth(&self) -> usize {
+        let mut memo: HashMap<*const RefCell<ValueData>, usize> = HashMap::new();
+        Value::depth(self, &mut memo)
+    }
+
+    // Counts each op type in the graph rooted at this value; leaves aren't counted.
+    pub fn op_histogram(&self) -> HashMap<String, usize> {
+        let mut topo: Vec<Value> = Vec::new();
+        let mut visited: HashSet<*const RefCell<ValueData>> = HashSet::new();
+        Value::build_topo(self.clone(), &mut visited, &mut topo);
+
+        let mut counts = HashMap::new();
+        for node in topo {
+            if let Some(ref op) = node.data.borrow()._op {
+                *counts.entry(op.name().to_string()).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    fn has_cycle(
+        node: &Value,
+        on_stack: &mut HashSet<*const RefCell<ValueData>>,
+        done: &mut HashSet<*const RefCell<ValueData>>,
+    ) -> bool {
+        let node_ptr = Rc::as_ptr(&node.data);
+        if on_stack.contains(&node_ptr) {
+            return true;
+        }
+        if done.contains(&node_ptr) {
+            return false;
+        }
+        on_stack.insert(node_ptr);
+        if let Some(ref parents) = node.data.borrow()._prev {
+            for parent in parents {
+                if Value::has_cycle(parent, on_stack, done) {
+                    return true;
+                }
+            }
+        }
+        on_stack.remove(&node_ptr);
+        done.insert(node_ptr);
+        false
+    }
+
+    // Checks for back-edges with a DFS recursion stack. The public API only
+    // builds new nodes from existing ones, so a cycle can't actually be
+    // constructed and this should always return true; it guards build_topo's
+    // assumptions if graph construction ever becomes more flexible.
+    pub fn is_dag(&self) -> bool {
+        let mut on_stack: HashSet<*const RefCell<ValueData>> = HashSet::new();
+        let mut done: HashSet<*const RefCell<ValueData>> = HashSet::new();
+        !Value::has_cycle(self, &mut on_stack, &mut done)
+    }
+
+    fn zero_graph_grads(&self) {
+        let mut topo: Vec<Value> = Vec::new();
+        let mut visited: HashSet<*const RefCell<ValueData>> = HashSet::new();
+        Value::build_topo(self.clone(), &mut visited, &mut topo);
+
+        for node in topo {
+            node.zero_grad();
+        }
+    }
+
     pub fn data(&self) -> f64 {
         self.data.borrow().data
     }
@@ -123,6 +407,14 @@ impl Value {
         self.data.borrow().grad
     }
 
+    pub fn requires_grad(&self) -> bool {
+        self.data.borrow().requires_grad
+    }
+
+    pub fn set_requires_grad(&self, flag: bool) {
+        self.data.borrow_mut().requires_grad = flag;
+    }
+
     pub fn set_data(&self, val: f64) {
         self.data.borrow_mut().data = val;
     }
@@ -136,6 +428,85 @@ impl Value {
     pub fn zero_grad(&self) {
         self.data.borrow_mut().grad = 0.0;
     }
+
+    pub fn set_grad(&self, val: f64) {
+        self.data.borrow_mut().grad = val;
+    }
+}
+
+// A graph whose topological order is computed once and reused. Each forward
+// call recomputes every node's data in place from the current leaf values,
+// so repeated training on a fixed input (with parameters updated between
+// epochs) doesn't allocate a new graph every time.
+pub struct CompiledGraph {
+    topo: Vec<Value>,
+    inputs: Vec<usize>,
+}
+
+impl CompiledGraph {
+    // inputs are the leaves that run writes to, in the order given; every
+    // other leaf keeps its current value.
+    pub fn new(root: &Value, inputs: &[Value]) -> CompiledGraph {
+        let mut topo: Vec<Value> = Vec::new();
+        let mut visited: HashSet<*const RefCell<ValueData>> = HashSet::new();
+        Value::build_topo(root.clone(), &mut visited, &mut topo);
+        let inputs = inputs
+            .iter()
+            .map(|input| {
+                topo.iter()
+                    .position(|node| Rc::ptr_eq(&node.data, &input.data))
+                    .filter(|&i| topo[i].data.borrow()._op.is_none())
+                    .expect("compiled inputs must be leaves of the graph")
+            })
+            .collect();
+        CompiledGraph { topo, inputs }
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    // Writes inputs into the graph's leaves and replays the forward pass.
+    pub fn run(&self, inputs: &[f64]) -> f64 {
+        assert_eq!(
+            inputs.len(),
+            self.inputs.len(),
+            "expected {} inputs, got {}",
+            self.inputs.len(),
+            inputs.len()
+        );
+        for (&i, &x) in self.inputs.iter().zip(inputs.iter()) {
+            self.topo[i].set_data(x);
+        }
+        self.forward()
+    }
+
+    pub fn root(&self) -> &Value {
+        self.topo
+            .last()
+            .expect("compiled graph always contains its root")
+    }
+
+    // Returns the root's recomputed data.
+    pub fn forward(&self) -> f64 {
+        for node in &self.topo {
+            node.recompute();
+        }
+        self.root().data()
+    }
+
+    pub fn zero_grad(&self) {
+        for node in &self.topo {
+            node.zero_grad();
+        }
+    }
+
+    pub fn backward(&self) {
+        self.root().data.borrow_mut().grad = 1.0;
+        for node in self.topo.iter().rev() {
+            node.propagate_grad();
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -159,7 +530,7 @@ impl Add<f64> for Value {
     type Output = Value;
 
     fn add(self, other: f64) -> Value {
-        self + Value::new(other)
+        self + Value::constant(other)
     }
 }
 
@@ -167,7 +538,7 @@ impl Add<Value> for f64 {
     type Output = Value;
 
     fn add(self, other: Value) -> Value {
-        Value::new(self) + other
+        Value::constant(self) + other
     }
 }
 
@@ -186,7 +557,7 @@ impl Mul<f64> for Value {
     type Output = Value;
 
     fn mul(self, other: f64) -> Value {
-        self * Value::new(other)
+        self * Value::constant(other)
     }
 }
 
@@ -194,7 +565,7 @@ impl Mul<Value> for f64 {
     type Output = Value;
 
     fn mul(self, other: Value) -> Value {
-        Value::new(self) * other
+        Value::constant(self) * other
     }
 }
 
@@ -204,4 +575,299 @@ impl Sub for Value {
     fn sub(self, other: Value) -> Value {
         self + (other * -1.0)
     }
-}
\ No newline at end of file
+}
+
+// Row i holds the gradients of outputs[i] with respect to each input.
+// Grads in each output's graph are reset before its backward pass, so
+// accumulation from earlier rows does not leak into later ones.
+pub fn jacobian(outputs: &[Value], inputs: &[Value]) -> Vec<Vec<f64>> {
+    let mut rows = Vec::with_capacity(outputs.len());
+    for output in outputs {
+        output.zero_graph_grads();
+        for input in inputs {
+            input.zero_grad();
+        }
+        output.backward();
+        rows.push(inputs.iter().map(|input| input.grad()).collect());
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn jacobian_of_linear_map_is_weight_matrix() {
+        let w = [[2.0, -1.0], [0.5, 3.0]];
+        let x = [Value::new(0.7), Value::new(-1.2)];
+        let outputs: Vec<Value> = w
+            .iter()
+            .map(|row| x[0].clone() * row[0] + x[1].clone() * row[1])
+            .collect();
+        let jac = jacobian(&outputs, &x);
+        for (jac_row, w_row) in jac.iter().zip(w.iter()) {
+            for (&j, &wij) in jac_row.iter().zip(w_row.iter()) {
+                assert_close(j, wij);
+            }
+        }
+    }
+
+    #[test]
+    fn constant_receives_no_gradient() {
+        let a = Value::new(2.0);
+        let c = Value::constant(3.0);
+        let y = a.clone() * c.clone();
+        y.backward();
+        assert_close(a.grad(), 3.0);
+        assert_eq!(c.grad(), 0.0);
+        assert!(!c.requires_grad());
+    }
+
+    #[test]
+    fn frozen_value_keeps_its_grad() {
+        let w = Value::new(0.5);
+        let x = Value::new(2.0);
+        (w.clone() * x.clone()).backward();
+        assert_close(w.grad(), 2.0);
+
+        w.zero_grad();
+        w.set_requires_grad(false);
+        (w.clone() * x.clone()).backward();
+        assert_eq!(w.grad(), 0.0);
+        assert_close(x.grad(), 1.0);
+    }
+
+    #[test]
+    fn graph_depth_of_chain_and_tree() {
+        let mut chain = Value::new(1.0);
+        for i in 0..5 {
+            chain = chain + Value::new(i as f64);
+        }
+        assert_eq!(chain.graph_depth(), 5);
+
+        // Pairwise sums over 8 leaves: log2(8) levels.
+        let leaves: Vec<Value> = (0..8).map(|i| Value::new(i as f64)).collect();
+        let mut level = leaves.clone();
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| pair[0].clone() + pair[1].clone())
+                .collect();
+        }
+        assert_eq!(level[0].graph_depth(), 3);
+        assert_eq!(leaves[0].graph_depth(), 0);
+    }
+
+    #[test]
+    fn sign_ste_forward_sign_and_identity_grad() {
+        for (x, expected) in [(2.5, 1.0), (-0.3, -1.0), (0.0, 0.0)] {
+            let v = Value::new(x);
+            let s = v.clone().sign_ste();
+            assert_eq!(s.data(), expected);
+            s.backward();
+            assert_eq!(v.grad(), 1.0);
+        }
+    }
+
+    #[test]
+    fn op_histogram_counts_each_op() {
+        let a = Value::new(1.0);
+        let b = Value::new(2.0);
+        // Sub is Add plus Mul by -1.
+        let y = (a.clone() * b.clone() + a.clone()).relu() - b.clone().exp();
+        let counts = y.op_histogram();
+        assert_eq!(counts["Mul"], 2);
+        assert_eq!(counts["Add"], 2);
+        assert_eq!(counts["Relu"], 1);
+        assert_eq!(counts["Exp"], 1);
+        assert_eq!(counts.len(), 4);
+    }
+
+    // Every distinct node reachable from root, identified by its allocation.
+    fn graph_nodes(root: &Value, seen: &mut HashSet<*const RefCell<ValueData>>) {
+        let mut topo = Vec::new();
+        let mut visited = HashSet::new();
+        Value::build_topo(root.clone(), &mut visited, &mut topo);
+        seen.extend(visited);
+    }
+
+    #[test]
+    fn compiled_graph_reuses_nodes_across_epochs() {
+        let mlp = crate::MLP::new(2, &[4, 1]);
+        let rebuilt = mlp.deep_clone();
+        let x = [Value::constant(0.5), Value::constant(-1.0)];
+        let loss_of = |net: &crate::MLP| {
+            let diff = net.forward(&x)[0].clone() - Value::constant(0.3);
+            diff.clone() * diff
+        };
+
+        let compiled = loss_of(&mlp).compile(&[]);
+        let mut compiled_nodes = HashSet::new();
+        let mut rebuilt_nodes = HashSet::new();
+        let mut rebuilt_graphs = Vec::new();
+        let mut first_epoch = (0, 0);
+        for epoch in 0..5 {
+            compiled.zero_grad();
+            let compiled_loss = compiled.forward();
+            compiled.backward();
+            mlp.parameters().iter().for_each(|p| p.update(0.1));
+            graph_nodes(compiled.root(), &mut compiled_nodes);
+
+            rebuilt.zero_grad();
+            let loss = loss_of(&rebuilt);
+            loss.backward();
+            rebuilt.parameters().iter().for_each(|p| p.update(0.1));
+            graph_nodes(&loss, &mut rebuilt_nodes);
+            // Keep old graphs alive so their addresses can't be reused.
+            rebuilt_graphs.push(loss.clone());
+
+            assert_close(compiled_loss, loss.data());
+            if epoch == 0 {
+                first_epoch = (compiled_nodes.len(), rebuilt_nodes.len());
+            }
+        }
+        // The compiled graph keeps touching the same nodes; rebuilding
+        // allocates a fresh set of intermediate nodes every epoch.
+        assert_eq!(compiled_nodes.len(), first_epoch.0);
+        let shared = mlp.parameters().len() + x.len();
+        assert_eq!(rebuilt_nodes.len(), shared + 5 * (first_epoch.1 - shared));
+    }
+
+    #[test]
+    fn is_dag_accepts_shared_subgraphs_and_rejects_cycles() {
+        let a = Value::new(2.0);
+        let b = a.clone() * a.clone();
+        // Diamond: b feeds both branches, which meet again at y.
+        let y = b.clone().relu() + b.clone().exp() + a.clone();
+        assert!(y.is_dag());
+        assert!(Value::new(1.0).is_dag());
+
+        // Only reachable through private fields: make a depend on b.
+        a.data.borrow_mut()._prev = Some(vec![b.clone()]);
+        a.data.borrow_mut()._op = Some(Op::Relu);
+        assert!(!y.is_dag());
+        // Break the cycle so the Rcs can be freed.
+        a.data.borrow_mut()._prev = None;
+        assert!(y.is_dag());
+    }
+
+    #[test]
+    fn set_grad_overwrites_and_backward_accumulates() {
+        let x = Value::new(3.0);
+        x.set_grad(5.0);
+        assert_eq!(x.grad(), 5.0);
+        (x.clone() * 2.0).backward();
+        assert_close(x.grad(), 7.0);
+    }
+
+    #[test]
+    fn backward_from_scales_by_seed() {
+        let x = Value::new(1.5);
+        (x.clone() * 3.0).backward_from(2.0);
+        assert_close(x.grad(), 6.0);
+    }
+
+    #[test]
+    fn backward_to_skips_branches_that_miss_the_targets() {
+        let x = Value::new(2.0);
+        let w = Value::new(3.0);
+        let other = Value::new(4.0);
+        // other only reaches y through a branch that x never feeds.
+        let unrelated = other.clone().exp();
+        let y = x.clone() * w.clone() + unrelated.clone();
+        y.backward_to(std::slice::from_ref(&x));
+        assert_close(x.grad(), 3.0);
+        assert_eq!(other.grad(), 0.0);
+        assert_eq!(unrelated.grad(), 0.0);
+        // w shares a node with x but is not a target itself.
+        assert_eq!(w.grad(), 0.0);
+    }
+
+    #[test]
+    fn compiled_graph_replays_for_new_inputs() {
+        let a = Value::new(1.0);
+        let b = Value::new(1.0);
+        let graph = (a.clone() * b.clone() + a.clone().pow(3.0)).compile(&[a.clone(), b.clone()]);
+        assert_eq!(graph.num_inputs(), 2);
+        for (a, b) in [(2.0, 3.0), (-1.5, 0.5), (0.0, 7.0), (1.2, -4.0)] {
+            assert_close(graph.run(&[a, b]), a * b + a * a * a);
+        }
+    }
+
+    #[test]
+    fn compiled_graph_inputs_ignore_literals_and_frozen_leaves() {
+        let a = Value::new(1.0);
+        let b = Value::new(1.0);
+        let w = Value::new(3.0);
+        w.set_requires_grad(false);
+        let graph =
+            (a.clone() * 2.0 - b.clone() * w.clone() + 0.5).compile(&[b.clone(), a.clone()]);
+        assert_eq!(graph.num_inputs(), 2);
+        for (a, b) in [(2.0, 3.0), (-1.5, 0.5)] {
+            assert_close(graph.run(&[b, a]), a * 2.0 - b * 3.0 + 0.5);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "compiled inputs must be leaves of the graph")]
+    fn compile_rejects_inputs_outside_the_graph() {
+        let a = Value::new(1.0);
+        (a.clone() * 2.0).compile(&[Value::new(0.0)]);
+    }
+
+    #[test]
+    fn pow_of_negative_base() {
+        let x = Value::new(-2.0);
+        let cube = x.clone().pow(3.0);
+        assert_eq!(cube.data(), -8.0);
+        cube.backward();
+        // 3 * (-2)^2
+        assert_eq!(x.grad(), 12.0);
+
+        let root = Value::new(-2.0).pow(0.5);
+        assert!(root.data().is_nan());
+
+        let zero = Value::new(0.0);
+        let one = zero.clone().pow(0.0);
+        assert_eq!(one.data(), 1.0);
+        one.backward();
+        assert_eq!(zero.grad(), 0.0);
+    }
+
+    #[test]
+    fn checkpoint_matches_plain_grads_with_a_smaller_graph() {
+        let w = [Value::new(0.8), Value::new(-1.3)];
+        let block = {
+            let w = w.clone();
+            move |x: &[Value]| {
+                let h = (x[0].clone() * w[0].clone() + x[1].clone() * w[1].clone()).tanh();
+                vec![h.clone() * h.clone(), (h + x[0].clone()).exp()]
+            }
+        };
+        let loss = |outs: &[Value]| outs[0].clone() + outs[1].clone() * 0.5;
+
+        let x = [Value::new(0.4), Value::new(0.9)];
+        let plain = loss(&block(&x));
+        plain.backward();
+        let plain_grads: Vec<f64> = x.iter().chain(w.iter()).map(|v| v.grad()).collect();
+        let plain_nodes = plain.op_histogram().values().sum::<usize>();
+        x.iter().chain(w.iter()).for_each(|v| v.zero_grad());
+
+        let checkpointed = loss(&Value::checkpoint(&x, block));
+        assert_close(checkpointed.data(), plain.data());
+        checkpointed.backward();
+        for (v, g) in x.iter().chain(w.iter()).zip(plain_grads) {
+            assert_close(v.grad(), g);
+        }
+        // Only the outputs and the loss ops remain; f's internals are gone.
+        let counts = checkpointed.op_histogram();
+        assert_eq!(counts["Checkpoint"], 2);
+        assert!(counts.values().sum::<usize>() < plain_nodes);
+        assert!(checkpointed.graph_depth() < plain.graph_depth());
+    }
+}
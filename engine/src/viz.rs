@@ -0,0 +1,80 @@
+// Renders the loss curve as a width x height grid of characters, one '*' per
+// column. The top row is the maximum loss and the bottom row the minimum;
+// losses are resampled to fit the width.
+pub fn ascii_plot(losses: &[f64], width: usize, height: usize) -> String {
+    if losses.is_empty() || width == 0 || height == 0 {
+        return String::new();
+    }
+
+    let max = losses.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let min = losses.iter().cloned().fold(f64::INFINITY, f64::min);
+    let range = max - min;
+
+    let rows: Vec<usize> = (0..width)
+        .map(|col| {
+            let idx = if width == 1 {
+                0
+            } else {
+                col * (losses.len() - 1) / (width - 1)
+            };
+            if range > 0.0 {
+                ((max - losses[idx]) / range * (height - 1) as f64).round() as usize
+            } else {
+                height - 1
+            }
+        })
+        .collect();
+
+    let mut grid = vec![vec![' '; width]; height];
+    for (col, &row) in rows.iter().enumerate() {
+        grid[row][col] = '*';
+    }
+
+    grid.iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Exponential moving average: s[0] = losses[0], s[t] = alpha * losses[t] + (1 - alpha) * s[t-1].
+// alpha = 1.0 returns the input unchanged; smaller values smooth more.
+pub fn smooth_losses(losses: &[f64], alpha: f64) -> Vec<f64> {
+    let mut smoothed = Vec::with_capacity(losses.len());
+    for &loss in losses {
+        let next = match smoothed.last() {
+            Some(&prev) => alpha * loss + (1.0 - alpha) * prev,
+            None => loss,
+        };
+        smoothed.push(next);
+    }
+    smoothed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_plot_of_decreasing_curve() {
+        let losses = [4.0, 3.0, 2.0, 1.0, 0.0];
+        let plot = ascii_plot(&losses, 5, 5);
+        let rows: Vec<&str> = plot.lines().collect();
+        assert_eq!(rows.len(), 5);
+        // One point per column, stepping down the diagonal.
+        for (i, row) in rows.iter().enumerate() {
+            assert_eq!(row.len(), 5);
+            assert_eq!(row.find('*'), Some(i));
+            assert_eq!(row.matches('*').count(), 1);
+        }
+    }
+
+    #[test]
+    fn smooth_losses_reduces_spikes() {
+        let spiky = [1.0, 5.0, 1.0, 5.0, 1.0, 5.0];
+        let total_variation = |xs: &[f64]| xs.windows(2).map(|w| (w[1] - w[0]).abs()).sum::<f64>();
+        let smoothed = smooth_losses(&spiky, 0.3);
+        assert_eq!(smoothed.len(), spiky.len());
+        assert!(total_variation(&smoothed) < total_variation(&spiky));
+        assert_eq!(smooth_losses(&spiky, 1.0), spiky.to_vec());
+    }
+}
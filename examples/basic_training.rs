@@ -1,24 +1,11 @@
-use engine::{MLP, value::Value};
-
-fn compute_loss(mlp: &MLP, xs: &[Vec<Value>], ys: &[Value]) -> f64 {
-    let mut total_loss = 0.0;
-    for (x, y) in xs.iter().zip(ys.iter()) {
-        let pred = mlp.forward(x);
-        let pred_val = &pred[0];
-
-        let diff = pred_val.clone() - y.clone();
-        let loss = diff.clone() * diff.clone();
-        total_loss += loss.data();
-    }
-    total_loss / xs.len() as f64
-}
+use engine::{MLP, loss::eval_loss, value::Value};
 
 fn main() {
     // Create a simple MLP: 2 inputs -> 16 hidden neurons -> 16 hidden neurons -> 1 output
     let mlp = MLP::new(2, &[16, 16, 1]);
 
     // Data: simple function y = x1 + x2
-    let xs = vec![
+    let xs = [
         vec![Value::new(0.0), Value::new(0.0)],
         vec![Value::new(0.0), Value::new(1.0)],
         vec![Value::new(1.0), Value::new(0.0)],
@@ -29,7 +16,7 @@ fn main() {
         vec![Value::new(0.1), Value::new(0.9)],
     ];
 
-    let ys = vec![
+    let ys = [
         Value::new(0.0),   // 0 + 0 = 0
         Value::new(1.0),   // 0 + 1 = 1
         Value::new(1.0),   // 1 + 0 = 1
@@ -47,6 +34,13 @@ fn main() {
     let test_xs = xs[train_size..].to_vec();
     let test_ys = ys[train_size..].to_vec();
 
+    // Plain f64 copies of the test set for graph-free evaluation
+    let test_xs_f64: Vec<Vec<f64>> = test_xs
+        .iter()
+        .map(|x| x.iter().map(|v| v.data()).collect())
+        .collect();
+    let test_ys_f64: Vec<f64> = test_ys.iter().map(|y| y.data()).collect();
+
     // Training loop
     let learning_rate = 0.01;
     let epochs = 100;
@@ -84,9 +78,8 @@ fn main() {
             param.update(learning_rate);
         }
 
-        // Compute loss on test set (no gradients needed)
-        mlp.zero_grad();
-        let test_loss = compute_loss(&mlp, &test_xs, &test_ys);
+        // Compute loss on test set (no graph is built)
+        let test_loss = eval_loss(&mlp, &test_xs_f64, &test_ys_f64);
 
         // Print loss every 10 epochs
         if epoch % 10 == 0 {